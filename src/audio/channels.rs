@@ -0,0 +1,277 @@
+//! 声道布局模块
+//!
+//! 在设备不支持音源声道数时选择可用的声道数，并按 ITU-R BS.775 系数进行下混，单声道复制到左右声道进行上混
+
+use symphonia::core::audio::Channels;
+
+/// -3dB，中置和环绕声道下混到左右声道的系数
+const MINUS_3DB: f32 = std::f32::consts::FRAC_1_SQRT_2;
+
+/// 设备声道数对应的标准布局，按 WAVE 声道顺序排列
+pub fn output_layout(count: usize) -> Channels {
+    let front = Channels::FRONT_LEFT | Channels::FRONT_RIGHT;
+    match count {
+        1 => Channels::FRONT_CENTRE,
+        2 => front,
+        3 => front | Channels::FRONT_CENTRE,
+        4 => front | Channels::REAR_LEFT | Channels::REAR_RIGHT,
+        5 => front | Channels::FRONT_CENTRE | Channels::REAR_LEFT | Channels::REAR_RIGHT,
+        6 => front | Channels::FRONT_CENTRE | Channels::LFE1 | Channels::REAR_LEFT | Channels::REAR_RIGHT,
+        7 => {
+            front
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::REAR_CENTRE
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+        }
+        _ => {
+            front
+                | Channels::FRONT_CENTRE
+                | Channels::LFE1
+                | Channels::REAR_LEFT
+                | Channels::REAR_RIGHT
+                | Channels::SIDE_LEFT
+                | Channels::SIDE_RIGHT
+        }
+    }
+}
+
+/// 从设备支持的声道数中选择输出声道数，优先使用音源声道数，其次是不少于音源的最少声道数，最后是最多的声道数
+pub fn choose_channels(supported: &[usize], wanted: usize) -> Option<usize> {
+    if supported.contains(&wanted) {
+        return Some(wanted);
+    }
+    supported
+        .iter()
+        .copied()
+        .filter(|count| *count >= wanted)
+        .min()
+        .or_else(|| supported.iter().copied().max())
+}
+
+/// 声道位置下混到左右声道时的增益
+fn stereo_fold(position: Channels) -> (f32, f32) {
+    let left = Channels::REAR_LEFT
+        | Channels::SIDE_LEFT
+        | Channels::FRONT_LEFT_CENTRE
+        | Channels::REAR_LEFT_CENTRE
+        | Channels::FRONT_LEFT_WIDE
+        | Channels::FRONT_LEFT_HIGH
+        | Channels::TOP_FRONT_LEFT
+        | Channels::TOP_REAR_LEFT;
+    let right = Channels::REAR_RIGHT
+        | Channels::SIDE_RIGHT
+        | Channels::FRONT_RIGHT_CENTRE
+        | Channels::REAR_RIGHT_CENTRE
+        | Channels::FRONT_RIGHT_WIDE
+        | Channels::FRONT_RIGHT_HIGH
+        | Channels::TOP_FRONT_RIGHT
+        | Channels::TOP_REAR_RIGHT;
+
+    if position == Channels::FRONT_LEFT {
+        (1.0, 0.0)
+    } else if position == Channels::FRONT_RIGHT {
+        (0.0, 1.0)
+    } else if position == Channels::LFE1 || position == Channels::LFE2 {
+        // 低频声道不参与下混
+        (0.0, 0.0)
+    } else if left.contains(position) {
+        (MINUS_3DB, 0.0)
+    } else if right.contains(position) {
+        (0.0, MINUS_3DB)
+    } else {
+        (MINUS_3DB, MINUS_3DB)
+    }
+}
+
+/// 输出布局中没有该位置时可以替代的位置
+fn equivalent(position: Channels) -> Channels {
+    match position {
+        p if p == Channels::SIDE_LEFT => Channels::REAR_LEFT,
+        p if p == Channels::SIDE_RIGHT => Channels::REAR_RIGHT,
+        p if p == Channels::REAR_LEFT => Channels::SIDE_LEFT,
+        p if p == Channels::REAR_RIGHT => Channels::SIDE_RIGHT,
+        p => p,
+    }
+}
+
+/// 声道转换矩阵，作用于交错格式的音频
+pub struct ChannelMatrix {
+    input: usize,
+    output: usize,
+    /// 按输出声道排列，每行是各输入声道的增益
+    coeffs: Vec<f32>,
+}
+
+impl ChannelMatrix {
+    /// 创建从音源布局到输出声道数的转换矩阵，声道数相同时返回 None
+    pub fn new(input: Channels, output: usize) -> Option<Self> {
+        let input_count = input.count();
+        if input_count == output || input_count == 0 || output == 0 {
+            return None;
+        }
+
+        let outputs: Vec<Channels> = output_layout(output).iter().take(output).collect();
+        let inputs: Vec<Channels> = input.iter().collect();
+        let mut coeffs = vec![0.0f32; output * input_count];
+        let mut set = |o: usize, i: usize, gain: f32| coeffs[o * input_count + i] += gain;
+
+        let left = outputs.iter().position(|p| *p == Channels::FRONT_LEFT);
+        let right = outputs.iter().position(|p| *p == Channels::FRONT_RIGHT);
+
+        for (i, &position) in inputs.iter().enumerate() {
+            // 单声道音源复制到左右声道
+            if input_count == 1 {
+                match (left, right) {
+                    (Some(l), Some(r)) => {
+                        set(l, i, 1.0);
+                        set(r, i, 1.0);
+                    }
+                    _ => set(0, i, 1.0),
+                }
+                continue;
+            }
+
+            let direct = outputs
+                .iter()
+                .position(|p| *p == position)
+                .or_else(|| outputs.iter().position(|p| *p == equivalent(position)));
+            if let Some(o) = direct {
+                set(o, i, 1.0);
+                continue;
+            }
+
+            // 输出布局中没有的声道下混到左右声道，单声道输出取左右平均
+            let (gain_left, gain_right) = stereo_fold(position);
+            match (left, right) {
+                (Some(l), Some(r)) => {
+                    set(l, i, gain_left);
+                    set(r, i, gain_right);
+                }
+                _ => set(0, i, (gain_left + gain_right) / 2.0),
+            }
+        }
+
+        // 每行增益之和不超过 1，避免下混后削波
+        for row in coeffs.chunks_mut(input_count) {
+            let sum: f32 = row.iter().sum();
+            if sum > 1.0 {
+                row.iter_mut().for_each(|gain| *gain /= sum);
+            }
+        }
+
+        Some(Self {
+            input: input_count,
+            output,
+            coeffs,
+        })
+    }
+
+    /// 输出声道数
+    pub fn output_channels(&self) -> usize {
+        self.output
+    }
+
+    /// 转换交错格式的音频
+    pub fn apply(&self, samples: &[f32], output: &mut Vec<f32>) {
+        output.clear();
+        for frame in samples.chunks_exact(self.input) {
+            for row in self.coeffs.chunks_exact(self.input) {
+                output.push(row.iter().zip(frame).map(|(gain, sample)| gain * sample).sum());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn surround_51() -> Channels {
+        output_layout(6)
+    }
+
+    fn row(matrix: &ChannelMatrix, output: usize) -> &[f32] {
+        &matrix.coeffs[output * matrix.input..(output + 1) * matrix.input]
+    }
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn choose_channels_prefers_source_count() {
+        assert_eq!(choose_channels(&[2, 6, 8], 6), Some(6));
+        assert_eq!(choose_channels(&[2, 8], 6), Some(8));
+        assert_eq!(choose_channels(&[1, 2], 6), Some(2));
+        assert_eq!(choose_channels(&[], 2), None);
+    }
+
+    #[test]
+    fn same_channel_count_needs_no_matrix() {
+        assert!(ChannelMatrix::new(output_layout(2), 2).is_none());
+        assert!(ChannelMatrix::new(Channels::empty(), 2).is_none());
+    }
+
+    #[test]
+    fn mono_is_copied_to_left_and_right() {
+        let matrix = ChannelMatrix::new(Channels::FRONT_CENTRE, 2).unwrap();
+        let mut output = Vec::new();
+        matrix.apply(&[0.25, -0.5], &mut output);
+        assert_eq!(output, [0.25, 0.25, -0.5, -0.5]);
+    }
+
+    #[test]
+    fn surround_to_stereo_rows_sum_to_unity() {
+        let matrix = ChannelMatrix::new(surround_51(), 2).unwrap();
+        // 5.1 声道顺序: FL FR FC LFE RL RR
+        let sum = 1.0 + 2.0 * MINUS_3DB;
+        let left = row(&matrix, 0);
+        let right = row(&matrix, 1);
+        for (actual, expected) in left.iter().zip([1.0, 0.0, MINUS_3DB, 0.0, MINUS_3DB, 0.0]) {
+            assert_close(*actual, expected / sum);
+        }
+        for (actual, expected) in right.iter().zip([0.0, 1.0, MINUS_3DB, 0.0, 0.0, MINUS_3DB]) {
+            assert_close(*actual, expected / sum);
+        }
+        assert_close(left.iter().sum(), 1.0);
+        assert_close(right.iter().sum(), 1.0);
+    }
+
+    #[test]
+    fn surround_downmix_does_not_clip() {
+        let matrix = ChannelMatrix::new(surround_51(), 2).unwrap();
+        let mut output = Vec::new();
+        matrix.apply(&[1.0; 12], &mut output);
+        assert_eq!(output.len(), 4);
+        assert!(output.iter().all(|sample| *sample <= 1.0 + 1e-6));
+    }
+
+    #[test]
+    fn stereo_to_mono_averages() {
+        let matrix = ChannelMatrix::new(output_layout(2), 1).unwrap();
+        let mut output = Vec::new();
+        matrix.apply(&[1.0, 0.0, 0.5, -0.5], &mut output);
+        assert_eq!(output, [0.5, 0.0]);
+    }
+
+    #[test]
+    fn side_channels_fold_into_rear() {
+        let input = surround_51() | Channels::SIDE_LEFT | Channels::SIDE_RIGHT;
+        let matrix = ChannelMatrix::new(input, 6).unwrap();
+        assert_eq!(matrix.output_channels(), 6);
+        // 7.1 声道顺序: FL FR FC LFE RL RR SL SR
+        assert_eq!(row(&matrix, 0), [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        assert_eq!(row(&matrix, 4), [0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5, 0.0]);
+        assert_eq!(row(&matrix, 5), [0.0, 0.0, 0.0, 0.0, 0.0, 0.5, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn stereo_upmix_leaves_other_channels_silent() {
+        let matrix = ChannelMatrix::new(output_layout(2), 6).unwrap();
+        let mut output = Vec::new();
+        matrix.apply(&[0.25, -0.25], &mut output);
+        assert_eq!(output, [0.25, -0.25, 0.0, 0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,153 @@
+//! 交叉淡化模块
+//!
+//! 在轨道切换时将前一首的结尾与下一首的开头重叠混合
+
+use std::collections::VecDeque;
+use std::f32::consts::FRAC_PI_2;
+use symphonia::core::audio::{AudioBuffer, AudioBufferRef, Signal, SignalSpec};
+
+/// 淡化曲线
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CrossfadeCurve {
+    /// 线性
+    Linear,
+    /// 等功率
+    EqualPower,
+}
+
+impl CrossfadeCurve {
+    /// 根据曲线编号获取淡化曲线
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(CrossfadeCurve::Linear),
+            1 => Some(CrossfadeCurve::EqualPower),
+            _ => None,
+        }
+    }
+
+    /// 根据淡化进度(0.0-1.0)计算淡出和淡入增益
+    pub fn gains(self, progress: f32) -> (f32, f32) {
+        let progress = progress.clamp(0.0, 1.0);
+        match self {
+            CrossfadeCurve::Linear => (1.0 - progress, progress),
+            CrossfadeCurve::EqualPower => {
+                let angle = progress * FRAC_PI_2;
+                (angle.cos(), angle.sin())
+            }
+        }
+    }
+}
+
+/// 交叉淡化混音器
+pub struct Crossfader {
+    curve: CrossfadeCurve,
+    spec: SignalSpec,
+    /// 淡化总帧数
+    total_frames: usize,
+    /// 已淡化帧数
+    position: usize,
+    /// 下一首已解码但尚未混合的音频(按声道)
+    incoming: Vec<VecDeque<f32>>,
+}
+
+impl Crossfader {
+    /// 创建交叉淡化混音器
+    pub fn new(curve: CrossfadeCurve, spec: SignalSpec, total_frames: usize) -> Self {
+        Self {
+            curve,
+            spec,
+            total_frames,
+            position: 0,
+            incoming: vec![VecDeque::new(); spec.channels.count()],
+        }
+    }
+
+    /// 音频规格
+    pub fn spec(&self) -> SignalSpec {
+        self.spec
+    }
+
+    /// 淡化是否已完成
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.total_frames
+    }
+
+    /// 已缓存的下一首帧数
+    pub fn buffered_frames(&self) -> usize {
+        self.incoming.first().map_or(0, VecDeque::len)
+    }
+
+    /// 缓存下一首的解码数据
+    pub fn push_incoming(&mut self, decoded: AudioBufferRef<'_>) {
+        let buf = Self::to_f32(&decoded);
+        for (c, fifo) in self.incoming.iter_mut().enumerate() {
+            fifo.extend(buf.chan(c));
+        }
+    }
+
+    /// 将前一首的一段音频与缓存的下一首混合
+    pub fn mix(&mut self, outgoing: AudioBufferRef<'_>) -> AudioBuffer<f32> {
+        let mut buf = Self::to_f32(&outgoing);
+        let gains = self.next_gains(buf.frames());
+
+        for (c, fifo) in self.incoming.iter_mut().enumerate() {
+            for (sample, (gain_out, gain_in)) in buf.chan_mut(c).iter_mut().zip(gains.iter()) {
+                let incoming = fifo.pop_front().unwrap_or(0.0);
+                *sample = *sample * gain_out + incoming * gain_in;
+            }
+        }
+
+        buf
+    }
+
+    /// 取出剩余的缓存，并继续应用未完成的淡入
+    pub fn drain(&mut self) -> Option<AudioBuffer<f32>> {
+        let frames = self.buffered_frames();
+        if frames == 0 {
+            return None;
+        }
+
+        let mut buf = AudioBuffer::<f32>::new(frames as u64, self.spec);
+        buf.render_reserved(Some(frames));
+        for (c, fifo) in self.incoming.iter_mut().enumerate() {
+            for (sample, incoming) in buf.chan_mut(c).iter_mut().zip(fifo.drain(..)) {
+                *sample = incoming;
+            }
+        }
+
+        self.apply_fade_in(&mut buf);
+        Some(buf)
+    }
+
+    /// 对下一首后续的音频应用剩余的淡入
+    pub fn fade_in(&mut self, decoded: AudioBufferRef<'_>) -> AudioBuffer<f32> {
+        let mut buf = Self::to_f32(&decoded);
+        self.apply_fade_in(&mut buf);
+        buf
+    }
+
+    fn apply_fade_in(&mut self, buf: &mut AudioBuffer<f32>) {
+        let gains = self.next_gains(buf.frames());
+        for c in 0..self.spec.channels.count() {
+            for (sample, (_, gain_in)) in buf.chan_mut(c).iter_mut().zip(gains.iter()) {
+                *sample *= gain_in;
+            }
+        }
+    }
+
+    /// 计算接下来若干帧的增益并推进淡化进度
+    fn next_gains(&mut self, frames: usize) -> Vec<(f32, f32)> {
+        let total = self.total_frames.max(1) as f32;
+        let gains = (self.position..self.position + frames)
+            .map(|frame| self.curve.gains(frame as f32 / total))
+            .collect();
+        self.position += frames;
+        gains
+    }
+
+    fn to_f32(decoded: &AudioBufferRef<'_>) -> AudioBuffer<f32> {
+        let mut buf = decoded.make_equivalent::<f32>();
+        decoded.convert(&mut buf);
+        buf
+    }
+}
@@ -0,0 +1,132 @@
+//! 输出设备模块
+//!
+//! 枚举音频后端、输出设备及其支持的配置，并按名称或ID查找后端和设备
+//!
+//! Linux 上默认使用 ALSA，PulseAudio 和 PipeWire 通过 ALSA 的 default 设备输出；启用 jack 特性后可以选择 JACK 后端
+
+use crate::audio::types::{AudioOutputError, Result};
+use cpal::traits::{DeviceTrait, HostTrait};
+use serde::Serialize;
+
+/// 音频后端信息
+#[derive(Debug, Clone, Serialize)]
+pub struct HostInfo {
+    pub name: &'static str,
+    pub is_default: bool,
+}
+
+/// 枚举当前平台可用的音频后端
+pub fn list_hosts() -> Vec<HostInfo> {
+    let default_id = cpal::default_host().id();
+    cpal::available_hosts()
+        .into_iter()
+        .map(|id| HostInfo {
+            name: id.name(),
+            is_default: id == default_id,
+        })
+        .collect()
+}
+
+/// 导出后端列表为 JSON
+pub fn hosts_to_json(hosts: &[HostInfo]) -> String {
+    serde_json::to_string(hosts).unwrap_or_default()
+}
+
+/// 按名称查找音频后端，名称不区分大小写，未指定名称时使用默认后端
+pub fn find_host(name: Option<&str>) -> Result<cpal::Host> {
+    let Some(name) = name else {
+        return Ok(cpal::default_host());
+    };
+
+    let id = cpal::available_hosts()
+        .into_iter()
+        .find(|id| id.name().eq_ignore_ascii_case(name))
+        .ok_or(AudioOutputError::HostUnavailable)?;
+    cpal::host_from_id(id).map_err(|_| AudioOutputError::HostUnavailable)
+}
+
+/// 设备支持的输出配置
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceConfig {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: &'static str,
+}
+
+/// 输出设备信息
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceInfo {
+    /// 枚举顺序中的ID，设备插拔后可能变化
+    pub id: usize,
+    pub name: String,
+    pub is_default: bool,
+    pub configs: Vec<DeviceConfig>,
+}
+
+/// 枚举音频后端的输出设备
+pub fn list_output_devices(host: Option<&str>) -> Result<Vec<DeviceInfo>> {
+    let host = find_host(host)?;
+    let default_name = host.default_output_device().and_then(|device| device.name().ok());
+    let devices = host
+        .output_devices()
+        .map_err(|_| AudioOutputError::DeviceNotFound)?;
+
+    Ok(devices
+        .enumerate()
+        .filter_map(|(id, device)| {
+            let name = device.name().ok()?;
+            let configs = device
+                .supported_output_configs()
+                .map(|configs| {
+                    configs
+                        .map(|config| DeviceConfig {
+                            channels: config.channels(),
+                            min_sample_rate: config.min_sample_rate().0,
+                            max_sample_rate: config.max_sample_rate().0,
+                            sample_format: match config.sample_format() {
+                                cpal::SampleFormat::I16 => "i16",
+                                cpal::SampleFormat::U16 => "u16",
+                                cpal::SampleFormat::F32 => "f32",
+                            },
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(DeviceInfo {
+                id,
+                is_default: default_name.as_ref() == Some(&name),
+                name,
+                configs,
+            })
+        })
+        .collect())
+}
+
+/// 导出设备列表为 JSON
+pub fn devices_to_json(devices: &[DeviceInfo]) -> String {
+    serde_json::to_string(devices).unwrap_or_default()
+}
+
+/// 在音频后端中按名称查找输出设备，未指定名称时使用默认设备
+pub fn find_output_device(host: Option<&str>, name: Option<&str>) -> Result<cpal::Device> {
+    let host = find_host(host)?;
+    let Some(name) = name else {
+        return host.default_output_device().ok_or(AudioOutputError::DeviceNotFound);
+    };
+
+    host.output_devices()
+        .map_err(|_| AudioOutputError::DeviceNotFound)?
+        .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+        .ok_or(AudioOutputError::DeviceNotFound)
+}
+
+/// 按音频后端中枚举顺序的ID获取设备名称
+pub fn device_name_by_id(host: Option<&str>, id: usize) -> Result<String> {
+    find_host(host)?
+        .output_devices()
+        .map_err(|_| AudioOutputError::DeviceNotFound)?
+        .nth(id)
+        .and_then(|device| device.name().ok())
+        .ok_or(AudioOutputError::DeviceNotFound)
+}
@@ -0,0 +1,143 @@
+//! 抖动模块
+//!
+//! 将 f32 音频量化为 16 位整数时加入 TPDF 抖动，可选一阶噪声整形将量化噪声推向高频
+
+/// 16 位整数的满幅值
+const I16_SCALE: f32 = 32767.0;
+
+/// TPDF 抖动量化器，按声道保存噪声整形的误差
+pub struct Dither {
+    channels: usize,
+    /// xorshift 随机数状态
+    state: u32,
+    /// 各声道上一个采样的量化误差
+    error: Vec<f32>,
+}
+
+impl Dither {
+    /// 创建抖动量化器
+    pub fn new(channels: usize) -> Self {
+        Self {
+            channels: channels.max(1),
+            state: 0x9E37_79B9,
+            error: vec![0.0; channels.max(1)],
+        }
+    }
+
+    /// 量化交错格式的音频，关闭噪声整形时清除已保存的误差
+    pub fn quantize(&mut self, samples: &[f32], output: &mut [i16], noise_shaping: bool) {
+        if !noise_shaping {
+            self.error.fill(0.0);
+        }
+
+        for (i, (sample, output)) in samples.iter().zip(output.iter_mut()).enumerate() {
+            let c = i % self.channels;
+            let scaled = sample.clamp(-1.0, 1.0) * I16_SCALE;
+            let shaped = scaled - self.error[c];
+            let quantized = (shaped + self.tpdf()).round().clamp(i16::MIN as f32, i16::MAX as f32);
+            if noise_shaping {
+                self.error[c] = quantized - shaped;
+            }
+            *output = quantized as i16;
+        }
+    }
+
+    /// 幅度为 ±1 LSB 的三角分布噪声
+    fn tpdf(&mut self) -> f32 {
+        self.uniform() - self.uniform()
+    }
+
+    fn uniform(&mut self) -> f32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+        self.state as f32 / u32::MAX as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAMES: usize = 48000;
+
+    /// 量化结果与原始采样之差的总和，即低频方向的误差
+    fn error_sum(samples: &[f32], output: &[i16]) -> f32 {
+        samples
+            .iter()
+            .zip(output)
+            .map(|(sample, output)| *output as f32 - sample * I16_SCALE)
+            .sum()
+    }
+
+    fn ramp() -> Vec<f32> {
+        (0..FRAMES).map(|i| (i as f32 / FRAMES as f32) * 0.01 + 0.1).collect()
+    }
+
+    #[test]
+    fn tpdf_stays_within_one_lsb() {
+        let mut dither = Dither::new(1);
+        let noise: Vec<f32> = (0..FRAMES).map(|_| dither.tpdf()).collect();
+        assert!(noise.iter().all(|n| (-1.0..=1.0).contains(n)));
+        let mean = noise.iter().sum::<f32>() / FRAMES as f32;
+        assert!(mean.abs() < 0.01, "mean {mean}");
+    }
+
+    #[test]
+    fn quantized_output_stays_near_input() {
+        let mut dither = Dither::new(2);
+        let samples = vec![0.5f32; FRAMES];
+        let mut output = vec![0i16; FRAMES];
+        dither.quantize(&samples, &mut output, false);
+
+        let scaled = 0.5 * I16_SCALE;
+        assert!(output.iter().all(|o| (*o as f32 - scaled).abs() <= 1.5));
+        let mean = output.iter().map(|o| *o as f64).sum::<f64>() / FRAMES as f64;
+        assert!((mean - scaled as f64).abs() < 0.05, "mean {mean}");
+    }
+
+    #[test]
+    fn silence_dithers_around_zero() {
+        let mut dither = Dither::new(1);
+        let mut output = vec![0i16; FRAMES];
+        dither.quantize(&vec![0.0; FRAMES], &mut output, false);
+        assert!(output.iter().all(|o| (-1..=1).contains(o)));
+        assert!(output.iter().any(|o| *o != 0));
+    }
+
+    #[test]
+    fn full_scale_is_clamped() {
+        let mut dither = Dither::new(1);
+        let mut output = vec![0i16; 4];
+        dither.quantize(&[2.0, -2.0, 1.0, -1.0], &mut output, true);
+        assert!(output[0] >= i16::MAX - 1 && output[2] >= i16::MAX - 1);
+        assert!(output[1] <= -i16::MAX + 1 && output[3] <= -i16::MAX + 1);
+    }
+
+    #[test]
+    fn noise_shaping_removes_low_frequency_error() {
+        let samples = ramp();
+        let mut output = vec![0i16; FRAMES];
+
+        // 一阶噪声整形的误差逐个抵消，总和不超过最后一个采样的误差
+        let mut dither = Dither::new(1);
+        dither.quantize(&samples, &mut output, true);
+        assert!(error_sum(&samples, &output).abs() < 2.0);
+
+        let mut dither = Dither::new(1);
+        dither.quantize(&samples, &mut output, false);
+        assert!(error_sum(&samples, &output).abs() > 10.0);
+    }
+
+    #[test]
+    fn noise_shaping_error_is_per_channel() {
+        let mut dither = Dither::new(2);
+        let samples: Vec<f32> = ramp().iter().flat_map(|s| [*s, -*s]).collect();
+        let mut output = vec![0i16; samples.len()];
+        dither.quantize(&samples, &mut output, true);
+
+        let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+        let left_output: Vec<i16> = output.iter().step_by(2).copied().collect();
+        assert!(error_sum(&left, &left_output).abs() < 2.0);
+    }
+}
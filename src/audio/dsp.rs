@@ -0,0 +1,390 @@
+//! 音频处理链模块
+//!
+//! 在音频回调中按顺序对输出音频应用一组可在播放期间增删和调整的处理器
+//!
+//! 处理链由音频回调独占，播放器通过控制端发送修改命令，音频回调无需加锁
+
+use crate::audio::gain::GainRamp;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// 音量处理器的固定ID，该处理器始终位于处理链中
+pub const VOLUME_PROCESSOR_ID: u64 = 0;
+
+/// 插入、移除和旁路处理器时干湿混合的渐变时长(毫秒)
+const MIX_RAMP_MS: u64 = 20;
+
+/// 音频处理器
+///
+/// 处理器在音频回调中运行，不应阻塞或执行耗时操作
+pub trait AudioProcessor: Send {
+    /// 处理器名称
+    fn name(&self) -> &str;
+
+    /// 输出采样率或声道数变化时调用
+    fn configure(&mut self, sample_rate: u32, channels: usize);
+
+    /// 原地处理交错格式的 f32 音频
+    fn process(&mut self, samples: &mut [f32], channels: usize);
+
+    /// 清除内部状态，新建输出流时调用
+    fn reset(&mut self) {}
+
+    /// 输出播放到新轨道的第一个采样时调用
+    fn start_track(&mut self) {}
+
+    /// 设置参数，返回参数是否有效；参数变化应在处理器内部平滑过渡
+    fn set_parameter(&mut self, _name: &str, _value: f32) -> bool {
+        false
+    }
+}
+
+/// 处理链中的处理器
+struct Slot {
+    id: u64,
+    processor: Box<dyn AudioProcessor>,
+    /// 处理后音频的混合比例，插入、移除和旁路时渐变
+    mix: GainRamp,
+    /// 淡出完成后从处理链中移除
+    removing: bool,
+}
+
+/// 处理链修改命令，由控制线程发送，音频回调在处理前依次应用
+enum ChainCommand {
+    /// 在指定处理器之前插入，None 表示插入到末尾
+    Insert {
+        id: u64,
+        before: Option<u64>,
+        processor: Box<dyn AudioProcessor>,
+    },
+    Remove(u64),
+    SetBypassed(u64, bool),
+    SetParameter(u64, String, f32),
+    StartTrack,
+}
+
+/// 处理链的控制端，记录处理器列表并向音频回调发送修改命令，不会阻塞音频回调
+pub struct ChainController {
+    /// 处理器ID和名称，按处理顺序排列，不含正在移除的处理器
+    processors: Vec<(u64, String)>,
+    next_id: u64,
+    commands: Sender<ChainCommand>,
+}
+
+impl ChainController {
+    /// 处理器数量
+    pub fn len(&self) -> usize {
+        self.processors.len()
+    }
+
+    /// 处理链是否为空
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    /// 处理器ID和名称，按处理顺序排列
+    pub fn processors(&self) -> Vec<(u64, String)> {
+        self.processors.clone()
+    }
+
+    /// 在指定位置插入处理器并淡入，返回处理器ID
+    pub fn insert(&mut self, index: usize, processor: Box<dyn AudioProcessor>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let index = index.min(self.processors.len());
+        let before = self.processors.get(index).map(|(id, _)| *id);
+        self.processors.insert(index, (id, processor.name().to_string()));
+        self.send(ChainCommand::Insert { id, before, processor });
+        id
+    }
+
+    /// 淡出并移除处理器，音量处理器不能移除
+    pub fn remove(&mut self, id: u64) -> bool {
+        if id == VOLUME_PROCESSOR_ID || !self.contains(id) {
+            return false;
+        }
+        self.processors.retain(|(processor, _)| *processor != id);
+        self.send(ChainCommand::Remove(id));
+        true
+    }
+
+    /// 旁路或恢复处理器，音量处理器不能旁路
+    pub fn set_bypassed(&mut self, id: u64, bypassed: bool) -> bool {
+        if id == VOLUME_PROCESSOR_ID || !self.contains(id) {
+            return false;
+        }
+        self.send(ChainCommand::SetBypassed(id, bypassed));
+        true
+    }
+
+    /// 设置处理器参数，返回处理器是否存在；参数由音频回调应用，处理器不接受的参数被忽略
+    pub fn set_parameter(&mut self, id: u64, name: &str, value: f32) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+        self.send(ChainCommand::SetParameter(id, name.to_string(), value));
+        true
+    }
+
+    /// 通知所有处理器开始播放新轨道
+    pub fn start_track(&mut self) {
+        self.send(ChainCommand::StartTrack);
+    }
+
+    fn contains(&self, id: u64) -> bool {
+        self.processors.iter().any(|(processor, _)| *processor == id)
+    }
+
+    fn send(&self, command: ChainCommand) {
+        // 处理链与控制端同时创建并由输出控制保留，接收端不会先于发送端释放
+        let _ = self.commands.send(command);
+    }
+}
+
+/// 音频处理链，由音频回调持有，通过控制端发送的命令修改
+pub struct ProcessorChain {
+    slots: Vec<Slot>,
+    sample_rate: u32,
+    channels: usize,
+    /// 干湿混合时保存的未处理音频
+    dry: Vec<f32>,
+    commands: Receiver<ChainCommand>,
+}
+
+impl ProcessorChain {
+    /// 创建处理链及其控制端，音量处理器作为第一个处理器
+    pub fn new(volume: Box<dyn AudioProcessor>) -> (Self, ChainController) {
+        let (sender, receiver) = mpsc::channel();
+        let controller = ChainController {
+            processors: vec![(VOLUME_PROCESSOR_ID, volume.name().to_string())],
+            next_id: VOLUME_PROCESSOR_ID + 1,
+            commands: sender,
+        };
+        let chain = Self {
+            slots: vec![Slot {
+                id: VOLUME_PROCESSOR_ID,
+                processor: volume,
+                mix: GainRamp::new(1.0),
+                removing: false,
+            }],
+            sample_rate: 0,
+            channels: 0,
+            dry: Vec::new(),
+            commands: receiver,
+        };
+        (chain, controller)
+    }
+
+    /// 依次应用控制端发送的修改命令，不会阻塞
+    pub fn apply_commands(&mut self) {
+        while let Ok(command) = self.commands.try_recv() {
+            let frames = self.mix_frames();
+            match command {
+                ChainCommand::Insert { id, before, mut processor } => {
+                    if self.sample_rate > 0 {
+                        processor.configure(self.sample_rate, self.channels);
+                    }
+                    let mut mix = GainRamp::new(0.0);
+                    mix.set_target(1.0, frames);
+                    let index = before
+                        .and_then(|before| self.slots.iter().position(|slot| slot.id == before))
+                        .unwrap_or(self.slots.len());
+                    self.slots.insert(
+                        index,
+                        Slot {
+                            id,
+                            processor,
+                            mix,
+                            removing: false,
+                        },
+                    );
+                }
+                ChainCommand::Remove(id) => {
+                    if let Some(slot) = self.slot_mut(id) {
+                        slot.removing = true;
+                        slot.mix.set_target(0.0, frames);
+                    }
+                }
+                ChainCommand::SetBypassed(id, bypassed) => {
+                    if let Some(slot) = self.slot_mut(id) {
+                        slot.mix.set_target(if bypassed { 0.0 } else { 1.0 }, frames);
+                    }
+                }
+                ChainCommand::SetParameter(id, name, value) => {
+                    if let Some(slot) = self.slot_mut(id) {
+                        slot.processor.set_parameter(&name, value);
+                    }
+                }
+                ChainCommand::StartTrack => self.start_track(),
+            }
+        }
+    }
+
+    /// 输出格式变化时重新配置所有处理器
+    pub fn prepare(&mut self, sample_rate: u32, channels: usize) {
+        if sample_rate == self.sample_rate && channels == self.channels {
+            return;
+        }
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+        for slot in &mut self.slots {
+            slot.processor.configure(sample_rate, channels);
+        }
+    }
+
+    /// 清除所有处理器的内部状态
+    pub fn reset(&mut self) {
+        for slot in &mut self.slots {
+            slot.processor.reset();
+        }
+    }
+
+    /// 通知所有处理器开始播放新轨道
+    pub fn start_track(&mut self) {
+        for slot in &mut self.slots {
+            slot.processor.start_track();
+        }
+    }
+
+    /// 按顺序处理交错格式的 f32 音频
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        let channels = channels.max(1);
+        for slot in &mut self.slots {
+            if !slot.mix.is_ramping() {
+                // 已旁路的处理器不参与处理
+                if slot.mix.target() > 0.0 {
+                    slot.processor.process(samples, channels);
+                }
+                continue;
+            }
+
+            self.dry.clear();
+            self.dry.extend_from_slice(samples);
+            slot.processor.process(samples, channels);
+            for (wet, dry) in samples.chunks_mut(channels).zip(self.dry.chunks(channels)) {
+                let mix = slot.mix.advance();
+                for (wet, dry) in wet.iter_mut().zip(dry) {
+                    *wet = dry + (*wet - dry) * mix;
+                }
+            }
+        }
+
+        self.slots.retain(|slot| !(slot.removing && slot.mix.is_silent()));
+    }
+
+    fn slot_mut(&mut self, id: u64) -> Option<&mut Slot> {
+        self.slots
+            .iter_mut()
+            .find(|slot| slot.id == id && !slot.removing)
+    }
+
+    fn mix_frames(&self) -> usize {
+        (MIX_RAMP_MS * self.sample_rate as u64 / 1000) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 先乘以增益再加上偏移的测试处理器
+    struct Affine {
+        gain: f32,
+        offset: f32,
+    }
+
+    impl Affine {
+        fn boxed(gain: f32, offset: f32) -> Box<dyn AudioProcessor> {
+            Box::new(Self { gain, offset })
+        }
+    }
+
+    impl AudioProcessor for Affine {
+        fn name(&self) -> &str {
+            "affine"
+        }
+
+        fn configure(&mut self, _sample_rate: u32, _channels: usize) {}
+
+        fn process(&mut self, samples: &mut [f32], _channels: usize) {
+            samples.iter_mut().for_each(|s| *s = *s * self.gain + self.offset);
+        }
+
+        fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+            match name {
+                "gain" => self.gain = value,
+                _ => return false,
+            }
+            true
+        }
+    }
+
+    fn chain() -> (ProcessorChain, ChainController) {
+        let (mut chain, controller) = ProcessorChain::new(Affine::boxed(1.0, 0.0));
+        chain.prepare(1000, 1);
+        (chain, controller)
+    }
+
+    /// 处理足够长的音频使渐变完成，返回最后一个采样
+    fn settle(chain: &mut ProcessorChain) -> f32 {
+        chain.apply_commands();
+        let mut samples = vec![1.0; 100];
+        chain.process(&mut samples, 1);
+        let mut samples = [1.0];
+        chain.process(&mut samples, 1);
+        samples[0]
+    }
+
+    #[test]
+    fn commands_apply_in_order_at_requested_position() {
+        let (mut chain, mut controller) = chain();
+        let add = controller.insert(0, Affine::boxed(1.0, 1.0));
+        let double = controller.insert(1, Affine::boxed(2.0, 0.0));
+        assert_eq!(
+            controller.processors().iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            [add, double, VOLUME_PROCESSOR_ID]
+        );
+
+        // 命令在音频回调应用前不影响处理
+        let mut samples = [1.0];
+        chain.process(&mut samples, 1);
+        assert_eq!(samples[0], 1.0);
+
+        assert_eq!(settle(&mut chain), 4.0);
+    }
+
+    #[test]
+    fn parameters_and_bypass_reach_processor() {
+        let (mut chain, mut controller) = chain();
+        let id = controller.insert(0, Affine::boxed(2.0, 0.0));
+        assert!(controller.set_parameter(id, "gain", 3.0));
+        assert_eq!(settle(&mut chain), 3.0);
+
+        assert!(controller.set_bypassed(id, true));
+        assert_eq!(settle(&mut chain), 1.0);
+        assert!(controller.set_bypassed(id, false));
+        assert_eq!(settle(&mut chain), 3.0);
+    }
+
+    #[test]
+    fn removed_processor_fades_out_and_is_dropped() {
+        let (mut chain, mut controller) = chain();
+        let id = controller.insert(0, Affine::boxed(2.0, 0.0));
+        settle(&mut chain);
+
+        assert!(controller.remove(id));
+        assert_eq!(controller.len(), 1);
+        assert!(!controller.remove(id));
+        assert!(!controller.set_parameter(id, "gain", 3.0));
+        assert_eq!(settle(&mut chain), 1.0);
+        assert_eq!(chain.slots.len(), 1);
+    }
+
+    #[test]
+    fn volume_processor_cannot_be_removed_or_bypassed() {
+        let (_chain, mut controller) = chain();
+        assert!(!controller.remove(VOLUME_PROCESSOR_ID));
+        assert!(!controller.set_bypassed(VOLUME_PROCESSOR_ID, true));
+        assert!(controller.set_parameter(VOLUME_PROCESSOR_ID, "gain", 0.5));
+    }
+}
@@ -0,0 +1,716 @@
+//! 均衡器模块
+//!
+//! 提供基于双二阶滤波器的 10 段图形均衡和参数均衡，以及内置和可导入导出的预设
+
+use crate::audio::dsp::AudioProcessor;
+use crate::audio::gain::{GainRamp, db_to_gain};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// 图形均衡各频段的中心频率(Hz)
+pub const GRAPHIC_FREQUENCIES: [f32; 10] = [
+    31.0, 62.0, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+];
+/// 图形均衡的品质因数，约为一个倍频程带宽
+pub const GRAPHIC_Q: f32 = 1.41;
+/// 频段增益上限(dB)
+pub const MAX_BAND_GAIN_DB: f32 = 12.0;
+/// 参数均衡的最大频段数
+pub const MAX_PARAMETRIC_BANDS: usize = 16;
+
+/// 每次处理时增益向目标靠近的最大步长(dB)，避免调整时产生爆音
+const GAIN_STEP_DB: f32 = 0.5;
+
+/// 滤波器类型
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterType {
+    /// 峰值
+    Peak,
+    /// 低频搁架
+    LowShelf,
+    /// 高频搁架
+    HighShelf,
+}
+
+impl FilterType {
+    /// 根据类型编号获取滤波器类型
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(FilterType::Peak),
+            1 => Some(FilterType::LowShelf),
+            2 => Some(FilterType::HighShelf),
+            _ => None,
+        }
+    }
+}
+
+/// 均衡模式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EqMode {
+    /// 10 段图形均衡，只能调整增益
+    Graphic,
+    /// 参数均衡，可调整频段数量、类型、频率和品质因数
+    Parametric,
+}
+
+impl EqMode {
+    /// 根据模式编号获取均衡模式
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(EqMode::Graphic),
+            1 => Some(EqMode::Parametric),
+            _ => None,
+        }
+    }
+}
+
+/// 均衡频段
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EqBand {
+    /// 滤波器类型
+    #[serde(rename = "type")]
+    pub filter_type: FilterType,
+    /// 中心或转折频率(Hz)
+    pub frequency: f32,
+    /// 增益(dB)
+    #[serde(rename = "gain")]
+    pub gain_db: f32,
+    /// 品质因数
+    pub q: f32,
+}
+
+impl EqBand {
+    /// 图形均衡的第 index 个频段
+    fn graphic(index: usize, gain_db: f32) -> Self {
+        Self {
+            filter_type: FilterType::Peak,
+            frequency: GRAPHIC_FREQUENCIES[index],
+            gain_db,
+            q: GRAPHIC_Q,
+        }
+    }
+
+    /// 频段参数是否有效
+    pub fn is_valid(&self) -> bool {
+        (20.0..=20000.0).contains(&self.frequency)
+            && (-MAX_BAND_GAIN_DB..=MAX_BAND_GAIN_DB).contains(&self.gain_db)
+            && (0.1..=10.0).contains(&self.q)
+    }
+}
+
+/// 均衡器设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqSettings {
+    /// 均衡模式
+    pub mode: EqMode,
+    /// 前级增益(dB)，用于抵消提升频段带来的削波
+    #[serde(rename = "preamp", default)]
+    pub preamp_db: f32,
+    /// 频段
+    pub bands: Vec<EqBand>,
+}
+
+impl EqSettings {
+    /// 按 10 个频段的增益创建图形均衡设置
+    pub fn graphic(gains: [f32; 10], preamp_db: f32) -> Self {
+        Self {
+            mode: EqMode::Graphic,
+            preamp_db,
+            bands: gains
+                .iter()
+                .enumerate()
+                .map(|(index, gain_db)| EqBand::graphic(index, *gain_db))
+                .collect(),
+        }
+    }
+
+    /// 设置是否有效
+    pub fn is_valid(&self) -> bool {
+        let bands_valid = match self.mode {
+            EqMode::Graphic => {
+                self.bands.len() == GRAPHIC_FREQUENCIES.len()
+                    && self.bands.iter().enumerate().all(|(index, band)| {
+                        band.filter_type == FilterType::Peak
+                            && band.frequency == GRAPHIC_FREQUENCIES[index]
+                    })
+            }
+            EqMode::Parametric => self.bands.len() <= MAX_PARAMETRIC_BANDS,
+        };
+        bands_valid
+            && self.bands.iter().all(EqBand::is_valid)
+            && (-MAX_BAND_GAIN_DB..=MAX_BAND_GAIN_DB).contains(&self.preamp_db)
+    }
+
+    /// 切换均衡模式，切换到图形均衡时保留对应频率的增益
+    pub fn set_mode(&mut self, mode: EqMode) {
+        if mode == self.mode {
+            return;
+        }
+        if mode == EqMode::Graphic {
+            let mut gains = [0.0; 10];
+            for (gain, frequency) in gains.iter_mut().zip(GRAPHIC_FREQUENCIES) {
+                if let Some(band) = self.bands.iter().find(|band| {
+                    band.filter_type == FilterType::Peak && band.frequency == frequency
+                }) {
+                    *gain = band.gain_db;
+                }
+            }
+            *self = Self::graphic(gains, self.preamp_db);
+        } else {
+            self.mode = mode;
+        }
+    }
+
+    /// 设置频段增益
+    pub fn set_band_gain(&mut self, index: usize, gain_db: f32) -> bool {
+        if !(-MAX_BAND_GAIN_DB..=MAX_BAND_GAIN_DB).contains(&gain_db) {
+            return false;
+        }
+        match self.bands.get_mut(index) {
+            Some(band) => {
+                band.gain_db = gain_db;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 设置参数均衡频段，索引等于频段数时追加
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> bool {
+        if self.mode != EqMode::Parametric || !band.is_valid() {
+            return false;
+        }
+        if index < self.bands.len() {
+            self.bands[index] = band;
+            true
+        } else if index == self.bands.len() && index < MAX_PARAMETRIC_BANDS {
+            self.bands.push(band);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 移除参数均衡频段
+    pub fn remove_band(&mut self, index: usize) -> bool {
+        if self.mode != EqMode::Parametric || index >= self.bands.len() {
+            return false;
+        }
+        self.bands.remove(index);
+        true
+    }
+
+    /// 设置前级增益(dB)
+    pub fn set_preamp(&mut self, preamp_db: f32) -> bool {
+        if !(-MAX_BAND_GAIN_DB..=MAX_BAND_GAIN_DB).contains(&preamp_db) {
+            return false;
+        }
+        self.preamp_db = preamp_db;
+        true
+    }
+
+    /// 导出为 JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+impl Default for EqSettings {
+    fn default() -> Self {
+        Self::graphic([0.0; 10], 0.0)
+    }
+}
+
+/// 均衡器预设
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqPreset {
+    /// 预设名称
+    pub name: String,
+    /// 均衡器设置
+    #[serde(flatten)]
+    pub settings: EqSettings,
+}
+
+impl EqPreset {
+    /// 从 JSON 读取预设，设置无效时返回 None
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(json)
+            .ok()
+            .filter(|preset| !preset.name.is_empty() && preset.settings.is_valid())
+    }
+
+    /// 导出为 JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// 内置预设，提升频段时降低前级增益以留出余量
+pub fn builtin_presets() -> Vec<EqPreset> {
+    let graphic = |name: &str, gains: [f32; 10]| {
+        let max_gain = gains.iter().fold(0.0f32, |max, gain| max.max(*gain));
+        let preamp_db = if max_gain > 0.0 { -max_gain } else { 0.0 };
+        EqPreset {
+            name: name.to_string(),
+            settings: EqSettings::graphic(gains, preamp_db),
+        }
+    };
+
+    vec![
+        graphic("Flat", [0.0; 10]),
+        graphic("Rock", [5.0, 4.0, 3.0, 1.0, -1.0, -1.0, 1.0, 3.0, 4.0, 5.0]),
+        graphic("Pop", [-1.0, 1.0, 3.0, 4.0, 3.0, 0.0, -1.0, -1.0, -1.0, -1.0]),
+        graphic("Jazz", [3.0, 2.0, 1.0, 2.0, -1.0, -1.0, 0.0, 1.0, 2.0, 3.0]),
+        graphic("Classical", [4.0, 3.0, 2.0, 1.0, 0.0, 0.0, 0.0, 2.0, 3.0, 4.0]),
+        graphic("Bass Boost", [7.0, 6.0, 5.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        graphic("Treble Boost", [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 3.0, 5.0, 6.0, 7.0]),
+        graphic("Vocal", [-2.0, -3.0, -2.0, 1.0, 3.0, 4.0, 4.0, 3.0, 1.0, 0.0]),
+        graphic("Electronic", [5.0, 4.0, 1.0, 0.0, -2.0, 2.0, 1.0, 1.0, 4.0, 5.0]),
+    ]
+}
+
+/// 按名称查找内置预设
+pub fn builtin_preset(name: &str) -> Option<EqPreset> {
+    builtin_presets()
+        .into_iter()
+        .find(|preset| preset.name.eq_ignore_ascii_case(name))
+}
+
+/// 均衡器控制，在播放器和均衡处理器之间共享
+pub struct EqualizerControl {
+    settings: Mutex<EqSettings>,
+    /// 设置版本，处理器据此判断是否需要更新
+    version: AtomicU64,
+}
+
+impl EqualizerControl {
+    /// 创建均衡器控制
+    pub fn new() -> Self {
+        Self {
+            settings: Mutex::new(EqSettings::default()),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// 当前设置
+    pub fn settings(&self) -> EqSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// 修改设置，返回修改是否成功
+    pub fn update<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut EqSettings) -> bool,
+    {
+        let mut settings = self.settings.lock().unwrap();
+        let updated = f(&mut settings);
+        if updated {
+            self.version.fetch_add(1, Ordering::Release);
+        }
+        updated
+    }
+}
+
+impl Default for EqualizerControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 双二阶滤波器，使用转置直接II型结构
+struct Biquad {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    /// 各声道的滤波器状态
+    state: Vec<[f32; 2]>,
+}
+
+impl Biquad {
+    fn new(channels: usize) -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+            state: vec![[0.0; 2]; channels],
+        }
+    }
+
+    /// 按 RBJ Audio EQ Cookbook 计算系数
+    fn set_coefficients(&mut self, band: &EqBand, gain_db: f32, sample_rate: u32) {
+        let frequency = band.frequency.min(sample_rate as f32 * 0.45);
+        let a = 10f32.powf(gain_db / 40.0);
+        let w0 = 2.0 * PI * frequency / sample_rate as f32;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * band.q);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        let (b0, b1, b2, a0, a1, a2) = match band.filter_type {
+            FilterType::Peak => (
+                1.0 + alpha * a,
+                -2.0 * cos,
+                1.0 - alpha * a,
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            FilterType::LowShelf => (
+                a * ((a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha),
+                2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                a * ((a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha),
+                (a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha,
+            ),
+            FilterType::HighShelf => (
+                a * ((a + 1.0) + (a - 1.0) * cos + sqrt_a_alpha),
+                -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                a * ((a + 1.0) + (a - 1.0) * cos - sqrt_a_alpha),
+                (a + 1.0) - (a - 1.0) * cos + sqrt_a_alpha,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - sqrt_a_alpha,
+            ),
+        };
+
+        self.b0 = b0 / a0;
+        self.b1 = b1 / a0;
+        self.b2 = b2 / a0;
+        self.a1 = a1 / a0;
+        self.a2 = a2 / a0;
+    }
+
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        for frame in samples.chunks_mut(channels) {
+            for (sample, state) in frame.iter_mut().zip(self.state.iter_mut()) {
+                let input = *sample;
+                let output = self.b0 * input + state[0];
+                state[0] = self.b1 * input - self.a1 * output + state[1];
+                state[1] = self.b2 * input - self.a2 * output;
+                *sample = output;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.iter_mut().for_each(|state| *state = [0.0; 2]);
+    }
+}
+
+/// 均衡频段的处理状态
+struct BandState {
+    band: EqBand,
+    /// 当前生效的增益(dB)，逐步靠近目标增益
+    gain_db: f32,
+    filter: Biquad,
+    /// 频段已删除，增益渐变到 0dB 后移除
+    removed: bool,
+}
+
+/// 均衡处理器
+pub struct Equalizer {
+    control: Arc<EqualizerControl>,
+    /// 已应用的设置版本
+    version: Option<u64>,
+    bands: Vec<BandState>,
+    preamp: GainRamp,
+    sample_rate: u32,
+    channels: usize,
+}
+
+impl Equalizer {
+    /// 创建均衡处理器
+    pub fn new(control: Arc<EqualizerControl>) -> Self {
+        Self {
+            control,
+            version: None,
+            bands: Vec::new(),
+            preamp: GainRamp::new(1.0),
+            sample_rate: 0,
+            channels: 0,
+        }
+    }
+
+    /// 读取新的设置，保留已有频段的滤波器状态，增益变化逐步过渡
+    fn sync_settings(&mut self) {
+        let version = self.control.version.load(Ordering::Acquire);
+        if self.version == Some(version) {
+            return;
+        }
+        // 设置正在修改时下次再同步，避免阻塞音频回调
+        let Ok(settings) = self.control.settings.try_lock() else {
+            return;
+        };
+        self.version = Some(version);
+
+        // 按序号保留已有频段的滤波器状态和当前增益，增益继续逐步变化，只在参数变化时重新计算系数
+        for (index, band) in settings.bands.iter().enumerate() {
+            match self.bands.get_mut(index) {
+                Some(state) => {
+                    let reshaped = state.band.filter_type != band.filter_type
+                        || state.band.frequency != band.frequency
+                        || state.band.q != band.q;
+                    state.band = *band;
+                    state.removed = false;
+                    if reshaped && self.sample_rate > 0 {
+                        state.filter.set_coefficients(&state.band, state.gain_db, self.sample_rate);
+                    }
+                }
+                // 新增的频段从 0dB 渐变到目标增益
+                None => {
+                    let mut state = BandState {
+                        band: *band,
+                        gain_db: 0.0,
+                        filter: Biquad::new(self.channels),
+                        removed: false,
+                    };
+                    if self.sample_rate > 0 {
+                        state.filter.set_coefficients(band, 0.0, self.sample_rate);
+                    }
+                    self.bands.push(state);
+                }
+            }
+        }
+        // 删除的频段渐变到 0dB 后再移除
+        for state in self.bands.iter_mut().skip(settings.bands.len()) {
+            state.band.gain_db = 0.0;
+            state.removed = true;
+        }
+
+        let frames = (self.sample_rate / 50) as usize;
+        self.preamp.set_target(db_to_gain(settings.preamp_db), frames);
+    }
+}
+
+impl AudioProcessor for Equalizer {
+    fn name(&self) -> &str {
+        "equalizer"
+    }
+
+    fn configure(&mut self, sample_rate: u32, channels: usize) {
+        self.sample_rate = sample_rate;
+        self.channels = channels;
+        for state in &mut self.bands {
+            state.filter = Biquad::new(channels);
+            state.filter.set_coefficients(&state.band, state.gain_db, sample_rate);
+        }
+    }
+
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        self.sync_settings();
+        if self.sample_rate == 0 {
+            return;
+        }
+
+        self.preamp.apply(samples, channels);
+        for state in &mut self.bands {
+            // 增益逐步变化，每次变化后重新计算系数
+            if state.gain_db != state.band.gain_db {
+                let delta = (state.band.gain_db - state.gain_db).clamp(-GAIN_STEP_DB, GAIN_STEP_DB);
+                state.gain_db += delta;
+                state.filter.set_coefficients(&state.band, state.gain_db, self.sample_rate);
+            }
+            // 增益为 0dB 的频段不改变音频，清空状态以便重新启用时不带入旧数据
+            if state.gain_db == 0.0 {
+                state.filter.reset();
+            } else {
+                state.filter.process(samples, channels);
+            }
+        }
+        self.bands.retain(|state| !(state.removed && state.gain_db == 0.0));
+    }
+
+    fn reset(&mut self) {
+        for state in &mut self.bands {
+            state.filter.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    fn sine(frequency: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * PI * frequency * i as f32 / RATE as f32).sin() * 0.5)
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0f32, |max, sample| max.max(sample.abs()))
+    }
+
+    fn equalizer(control: &Arc<EqualizerControl>) -> Equalizer {
+        let mut equalizer = Equalizer::new(control.clone());
+        equalizer.configure(RATE, 1);
+        equalizer
+    }
+
+    fn parametric(bands: Vec<EqBand>) -> EqSettings {
+        EqSettings {
+            mode: EqMode::Parametric,
+            preamp_db: 0.0,
+            bands,
+        }
+    }
+
+    fn band(frequency: f32, gain_db: f32) -> EqBand {
+        EqBand {
+            filter_type: FilterType::Peak,
+            frequency,
+            gain_db,
+            q: 1.0,
+        }
+    }
+
+    #[test]
+    fn zero_db_band_is_identity() {
+        for filter_type in [FilterType::Peak, FilterType::LowShelf, FilterType::HighShelf] {
+            let band = EqBand { filter_type, ..band(1000.0, 0.0) };
+            let mut filter = Biquad::new(1);
+            filter.set_coefficients(&band, 0.0, RATE);
+
+            let input = sine(440.0, 4800);
+            let mut output = input.clone();
+            filter.process(&mut output, 1);
+            for (input, output) in input.iter().zip(&output) {
+                assert!((input - output).abs() < 1e-5, "{filter_type:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn peak_band_boosts_center_frequency() {
+        let mut filter = Biquad::new(1);
+        filter.set_coefficients(&band(1000.0, 6.0), 6.0, RATE);
+
+        let mut samples = sine(1000.0, RATE as usize);
+        filter.process(&mut samples, 1);
+        let gain = peak(&samples[RATE as usize / 2..]) / 0.5;
+        assert!((gain - db_to_gain(6.0)).abs() < 0.02, "gain {gain}");
+
+        // 远离中心频率的信号基本不受影响
+        let mut filter = Biquad::new(1);
+        filter.set_coefficients(&band(1000.0, 6.0), 6.0, RATE);
+        let mut samples = sine(50.0, RATE as usize);
+        filter.process(&mut samples, 1);
+        let gain = peak(&samples[RATE as usize / 2..]) / 0.5;
+        assert!((gain - 1.0).abs() < 0.05, "gain {gain}");
+    }
+
+    #[test]
+    fn band_gain_ramps_to_target() {
+        let control = Arc::new(EqualizerControl::new());
+        let mut equalizer = equalizer(&control);
+        assert!(control.update(|settings| settings.set_band_gain(5, 3.0)));
+
+        let mut samples = vec![0.0; 480];
+        equalizer.process(&mut samples, 1);
+        assert_eq!(equalizer.bands[5].gain_db, GAIN_STEP_DB);
+        for _ in 0..10 {
+            equalizer.process(&mut samples, 1);
+        }
+        assert_eq!(equalizer.bands[5].gain_db, 3.0);
+    }
+
+    #[test]
+    fn settings_change_keeps_band_state() {
+        let control = Arc::new(EqualizerControl::new());
+        let mut equalizer = equalizer(&control);
+        control.update(|settings| settings.set_band_gain(5, 6.0));
+        let mut samples = vec![0.0; 480];
+        for _ in 0..4 {
+            equalizer.process(&mut samples, 1);
+        }
+        assert_eq!(equalizer.bands[5].gain_db, 2.0);
+
+        // 修改其他频段不影响已有频段的当前增益
+        control.update(|settings| settings.set_band_gain(2, -3.0));
+        equalizer.process(&mut samples, 1);
+        assert_eq!(equalizer.bands[5].gain_db, 2.5);
+        assert_eq!(equalizer.bands[2].gain_db, -GAIN_STEP_DB);
+    }
+
+    #[test]
+    fn added_band_starts_at_zero_and_removed_band_fades_out() {
+        let control = Arc::new(EqualizerControl::new());
+        control.update(|settings| {
+            *settings = parametric(vec![band(100.0, 2.0)]);
+            true
+        });
+        let mut equalizer = equalizer(&control);
+        let mut samples = vec![0.0; 480];
+        for _ in 0..4 {
+            equalizer.process(&mut samples, 1);
+        }
+
+        control.update(|settings| settings.set_band(1, band(4000.0, 4.0)));
+        equalizer.process(&mut samples, 1);
+        assert_eq!(equalizer.bands.len(), 2);
+        assert_eq!(equalizer.bands[1].gain_db, GAIN_STEP_DB);
+
+        for _ in 0..3 {
+            equalizer.process(&mut samples, 1);
+        }
+        assert_eq!(equalizer.bands[1].gain_db, 2.0);
+
+        // 删除的频段逐步回到 0dB 后移除
+        control.update(|settings| settings.remove_band(1));
+        equalizer.process(&mut samples, 1);
+        assert_eq!(equalizer.bands.len(), 2);
+        assert!(equalizer.bands[1].removed);
+        assert_eq!(equalizer.bands[1].gain_db, 1.5);
+        for _ in 0..3 {
+            equalizer.process(&mut samples, 1);
+        }
+        assert_eq!(equalizer.bands.len(), 1);
+        assert_eq!(equalizer.bands[0].band.frequency, 100.0);
+        assert_eq!(equalizer.bands[0].gain_db, 2.0);
+    }
+
+    #[test]
+    fn switching_to_graphic_keeps_matching_gains() {
+        let mut settings = parametric(vec![band(1000.0, 4.0), band(1234.0, 6.0)]);
+        settings.set_mode(EqMode::Graphic);
+        assert!(settings.is_valid());
+        assert_eq!(settings.bands[5].gain_db, 4.0);
+        assert_eq!(settings.bands.iter().filter(|band| band.gain_db != 0.0).count(), 1);
+    }
+
+    #[test]
+    fn settings_validation() {
+        let mut settings = EqSettings::default();
+        assert!(settings.is_valid());
+        assert!(!settings.set_band_gain(0, MAX_BAND_GAIN_DB + 1.0));
+        assert!(!settings.set_band(0, band(1000.0, 1.0)));
+        assert!(!settings.set_preamp(-20.0));
+
+        settings.set_mode(EqMode::Parametric);
+        assert!(!settings.set_band(0, band(10.0, 1.0)));
+        assert!(!settings.set_band(11, band(1000.0, 1.0)));
+        assert!(settings.set_band(10, band(1000.0, 1.0)));
+    }
+
+    #[test]
+    fn preset_json_round_trip() {
+        for preset in builtin_presets() {
+            assert!(preset.settings.is_valid(), "{}", preset.name);
+            assert_eq!(EqPreset::from_json(&preset.to_json()), Some(preset));
+        }
+
+        let mut preset = builtin_preset("rock").unwrap();
+        preset.settings.bands[0].frequency = 40.0;
+        assert_eq!(EqPreset::from_json(&preset.to_json()), None);
+        assert_eq!(EqPreset::from_json("{}"), None);
+    }
+}
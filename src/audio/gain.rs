@@ -0,0 +1,342 @@
+//! 增益模块
+//!
+//! 提供音量曲线、分贝换算、逐采样的增益渐变和峰值限制，避免音量突变产生爆音
+
+use crate::audio::dsp::AudioProcessor;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+/// 音量变化的默认渐变时长(毫秒)
+pub const VOLUME_RAMP_MS: u64 = 10;
+/// 暂停、恢复、开始和停止时的默认淡入淡出时长(毫秒)
+pub const DEFAULT_FADE_MS: u64 = 30;
+/// 对数音量曲线的最小音量(dB)，低于此值视为静音
+pub const MIN_VOLUME_DB: f32 = -60.0;
+/// 最大前级增益(dB)
+pub const MAX_PREAMP_DB: f32 = 12.0;
+
+/// 限制器阈值，略低于满幅以留出余量
+const LIMITER_THRESHOLD: f32 = 0.98;
+/// 限制器释放时长(毫秒)
+const LIMITER_RELEASE_MS: u32 = 100;
+
+/// 分贝换算为线性增益
+pub fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// 线性增益换算为分贝，静音时为负无穷
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.log10()
+}
+
+/// 音量曲线，将 0.0-1.0 的音量位置映射为线性增益
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolumeCurve {
+    /// 线性
+    Linear,
+    /// 三次方
+    Cubic,
+    /// 对数，在 MIN_VOLUME_DB 到 0dB 之间按分贝均匀分布
+    Logarithmic,
+}
+
+impl VolumeCurve {
+    /// 根据曲线编号获取音量曲线
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(VolumeCurve::Linear),
+            1 => Some(VolumeCurve::Cubic),
+            2 => Some(VolumeCurve::Logarithmic),
+            _ => None,
+        }
+    }
+
+    /// 音量位置对应的线性增益
+    pub fn gain(self, position: f32) -> f32 {
+        let position = position.clamp(0.0, 1.0);
+        match self {
+            VolumeCurve::Linear => position,
+            VolumeCurve::Cubic => position.powi(3),
+            VolumeCurve::Logarithmic if position == 0.0 => 0.0,
+            VolumeCurve::Logarithmic => db_to_gain((1.0 - position) * MIN_VOLUME_DB),
+        }
+    }
+
+    /// 线性增益对应的音量位置
+    pub fn position(self, gain: f32) -> f32 {
+        let gain = gain.clamp(0.0, 1.0);
+        match self {
+            VolumeCurve::Linear => gain,
+            VolumeCurve::Cubic => gain.cbrt(),
+            VolumeCurve::Logarithmic => (1.0 - gain_to_db(gain) / MIN_VOLUME_DB).clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// 增益渐变器，处理交错格式的 f32 音频
+pub struct GainRamp {
+    /// 当前增益
+    current: f32,
+    /// 目标增益
+    target: f32,
+    /// 每帧增益变化量
+    step: f32,
+    /// 剩余渐变帧数
+    remaining: usize,
+}
+
+impl GainRamp {
+    /// 创建增益渐变器
+    pub fn new(gain: f32) -> Self {
+        Self {
+            current: gain,
+            target: gain,
+            step: 0.0,
+            remaining: 0,
+        }
+    }
+
+    /// 目标增益
+    pub fn target(&self) -> f32 {
+        self.target
+    }
+
+    /// 渐变过程中的最大增益
+    pub fn peak(&self) -> f32 {
+        self.current.max(self.target)
+    }
+
+    /// 是否已渐变到静音
+    pub fn is_silent(&self) -> bool {
+        self.remaining == 0 && self.current == 0.0
+    }
+
+    /// 是否正在渐变
+    pub fn is_ramping(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// 前进一帧，返回该帧的增益
+    pub fn advance(&mut self) -> f32 {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            self.current = if self.remaining == 0 {
+                self.target
+            } else {
+                self.current + self.step
+            };
+        }
+        self.current
+    }
+
+    /// 在指定帧数内从当前增益渐变到目标增益
+    pub fn set_target(&mut self, target: f32, frames: usize) {
+        self.target = target;
+        if frames == 0 {
+            self.current = target;
+            self.remaining = 0;
+        } else {
+            self.step = (target - self.current) / frames as f32;
+            self.remaining = frames;
+        }
+    }
+
+    /// 对音频应用增益
+    pub fn apply(&mut self, samples: &mut [f32], channels: usize) {
+        if self.remaining == 0 {
+            if self.current != 1.0 {
+                samples.iter_mut().for_each(|sample| *sample *= self.current);
+            }
+            return;
+        }
+
+        for frame in samples.chunks_mut(channels.max(1)) {
+            let gain = self.advance();
+            frame.iter_mut().for_each(|sample| *sample *= gain);
+        }
+    }
+}
+
+/// 峰值限制器，前级增益高于 0dB 时防止削波
+pub struct Limiter {
+    /// 当前增益衰减
+    reduction: f32,
+    /// 每帧释放量
+    release: f32,
+}
+
+impl Limiter {
+    /// 创建限制器
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            reduction: 1.0,
+            release: 1000.0 / (LIMITER_RELEASE_MS * sample_rate.max(1)) as f32,
+        }
+    }
+
+    /// 是否正在衰减
+    pub fn is_active(&self) -> bool {
+        self.reduction < 1.0
+    }
+
+    /// 清除衰减状态
+    pub fn reset(&mut self) {
+        self.reduction = 1.0;
+    }
+
+    /// 限制交错格式的音频，超过阈值时立即衰减，之后逐渐释放
+    pub fn process(&mut self, samples: &mut [f32], channels: usize) {
+        for frame in samples.chunks_mut(channels.max(1)) {
+            let peak = frame.iter().fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            let target = if peak > LIMITER_THRESHOLD {
+                LIMITER_THRESHOLD / peak
+            } else {
+                1.0
+            };
+            self.reduction = if target < self.reduction {
+                target
+            } else {
+                (self.reduction + self.release).min(target)
+            };
+            if self.reduction < 1.0 {
+                frame.iter_mut().for_each(|sample| *sample *= self.reduction);
+            }
+        }
+    }
+}
+
+/// 音量控制，在播放器和音量处理器之间共享
+pub struct VolumeControl {
+    /// 是否暂停，暂停时淡出到静音
+    paused: AtomicBool,
+    /// 暂停后是否已淡出到静音
+    silent: AtomicBool,
+    /// 目标输出增益(f32 位模式)，包含音量曲线、静音和前级增益
+    volume: AtomicU32,
+    /// 渐变到目标输出增益的时长(毫秒)
+    volume_ramp_ms: AtomicU64,
+    /// 暂停、恢复、开始和停止时的淡入淡出时长(毫秒)
+    fade_ms: AtomicU64,
+}
+
+impl VolumeControl {
+    /// 创建音量控制
+    pub fn new() -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            silent: AtomicBool::new(false),
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            volume_ramp_ms: AtomicU64::new(0),
+            fade_ms: AtomicU64::new(DEFAULT_FADE_MS),
+        }
+    }
+
+    /// 是否暂停
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// 设置暂停状态
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Release);
+    }
+
+    /// 暂停后是否已淡出到静音
+    pub fn is_silent(&self) -> bool {
+        self.silent.load(Ordering::Acquire)
+    }
+
+    /// 目标输出增益
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.volume.load(Ordering::Relaxed))
+    }
+
+    /// 在指定时长(毫秒)内渐变到目标输出增益
+    pub fn set_volume(&self, volume: f32, ramp_ms: u64) {
+        self.volume_ramp_ms.store(ramp_ms, Ordering::Relaxed);
+        self.volume.store(volume.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 淡入淡出时长(毫秒)
+    pub fn fade_duration(&self) -> u64 {
+        self.fade_ms.load(Ordering::Relaxed)
+    }
+
+    /// 设置淡入淡出时长(毫秒)
+    pub fn set_fade_duration(&self, fade_ms: u64) {
+        self.fade_ms.store(fade_ms, Ordering::Relaxed);
+    }
+}
+
+impl Default for VolumeControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 音量处理器，应用增益渐变、暂停淡入淡出和限制器
+pub struct VolumeProcessor {
+    control: Arc<VolumeControl>,
+    ramp: GainRamp,
+    limiter: Limiter,
+    sample_rate: u32,
+    /// 上一次处理时的暂停状态，新建输出流时视为从暂停恢复以淡入
+    was_paused: bool,
+}
+
+impl VolumeProcessor {
+    /// 创建音量处理器
+    pub fn new(control: Arc<VolumeControl>) -> Self {
+        Self {
+            control,
+            ramp: GainRamp::new(0.0),
+            limiter: Limiter::new(0),
+            sample_rate: 0,
+            was_paused: true,
+        }
+    }
+}
+
+impl AudioProcessor for VolumeProcessor {
+    fn name(&self) -> &str {
+        "volume"
+    }
+
+    fn configure(&mut self, sample_rate: u32, _channels: usize) {
+        self.sample_rate = sample_rate;
+        self.limiter = Limiter::new(sample_rate);
+    }
+
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        // 暂停状态变化时按淡入淡出时长渐变，否则按音量变化的渐变时长
+        let paused = self.control.is_paused();
+        let target = if paused { 0.0 } else { self.control.volume() };
+        if target != self.ramp.target() {
+            let ramp_ms = if paused != self.was_paused {
+                self.control.fade_duration()
+            } else {
+                self.control.volume_ramp_ms.load(Ordering::Relaxed)
+            };
+            let frames = (ramp_ms * self.sample_rate as u64 / 1000) as usize;
+            self.ramp.set_target(target, frames);
+        }
+        self.was_paused = paused;
+
+        self.ramp.apply(samples, channels);
+        // 增益高于 0dB 时可能削波，释放完成前保持限制
+        if self.ramp.peak() > 1.0 || self.limiter.is_active() {
+            self.limiter.process(samples, channels);
+        }
+
+        self.control
+            .silent
+            .store(paused && self.ramp.is_silent(), Ordering::Release);
+    }
+
+    fn reset(&mut self) {
+        self.ramp = GainRamp::new(0.0);
+        self.limiter.reset();
+        self.was_paused = true;
+    }
+}
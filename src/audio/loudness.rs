@@ -0,0 +1,638 @@
+//! 响度模块
+//!
+//! 按 EBU R128 / ITU BS.1770 测量积分响度、响度范围和真峰值，并在播放时将音频平滑归一化到目标响度
+
+use crate::audio::dsp::AudioProcessor;
+use crate::audio::gain::{GainRamp, Limiter, db_to_gain};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+/// 默认目标响度(LUFS)
+pub const DEFAULT_TARGET_LUFS: f32 = -14.0;
+/// 目标响度下限(LUFS)
+pub const MIN_TARGET_LUFS: f32 = -40.0;
+/// 目标响度上限(LUFS)
+pub const MAX_TARGET_LUFS: f32 = -5.0;
+
+/// 归一化的最大提升(dB)
+const MAX_BOOST_DB: f32 = 12.0;
+/// 归一化的最大衰减(dB)
+const MAX_CUT_DB: f32 = 24.0;
+/// 归一化增益每秒的最大变化量(dB)
+const GAIN_SLEW_DB_PER_SEC: f32 = 3.0;
+
+/// 绝对门限(LUFS)
+const ABSOLUTE_GATE: f64 = -70.0;
+/// 积分响度的相对门限(LU)
+const INTEGRATED_RELATIVE_GATE: f64 = -10.0;
+/// 响度范围的相对门限(LU)
+const RANGE_RELATIVE_GATE: f64 = -20.0;
+/// 响度直方图的上限(LUFS)和分辨率(LU)
+const HISTOGRAM_MAX: f64 = 10.0;
+const HISTOGRAM_STEP: f64 = 0.1;
+/// 子块时长(毫秒)，瞬时响度由 4 个子块组成，短期响度由 30 个子块组成
+const SUB_BLOCK_MS: u64 = 100;
+const MOMENTARY_SUB_BLOCKS: usize = 4;
+const SHORT_TERM_SUB_BLOCKS: usize = 30;
+/// 真峰值插值滤波器每个相位的抽头数
+const TRUE_PEAK_TAPS: usize = 12;
+
+fn power_to_lufs(power: f64) -> f64 {
+    -0.691 + 10.0 * power.log10()
+}
+
+fn lufs_to_power(lufs: f64) -> f64 {
+    10f64.powf((lufs + 0.691) / 10.0)
+}
+
+/// K 计权滤波器的一级
+#[derive(Clone, Copy)]
+struct KFilter {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl KFilter {
+    /// 高架预滤波和 RLB 高通，系数按采样率重新计算
+    fn k_weighting(sample_rate: u32) -> [KFilter; 2] {
+        let rate = sample_rate as f64;
+
+        let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain_db / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = KFilter {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        };
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = KFilter {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+            z: [0.0; 2],
+        };
+
+        [shelf, high_pass]
+    }
+
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// 响度直方图，按 0.1 LU 分辨率统计高于绝对门限的块
+struct Histogram {
+    counts: Vec<u64>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        let bins = ((HISTOGRAM_MAX - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+        Self {
+            counts: vec![0; bins],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+    }
+
+    fn add(&mut self, lufs: f64) {
+        if lufs < ABSOLUTE_GATE || !lufs.is_finite() {
+            return;
+        }
+        let bin = ((lufs - ABSOLUTE_GATE) / HISTOGRAM_STEP) as usize;
+        let last = self.counts.len() - 1;
+        self.counts[bin.min(last)] += 1;
+    }
+
+    fn bin_lufs(bin: usize) -> f64 {
+        ABSOLUTE_GATE + (bin as f64 + 0.5) * HISTOGRAM_STEP
+    }
+
+    /// 高于门限的块按能量平均后的响度
+    fn gated_mean(&self, gate: f64) -> Option<f64> {
+        let (power, count) = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(bin, count)| **count > 0 && Self::bin_lufs(*bin) >= gate)
+            .fold((0.0, 0u64), |(power, total), (bin, count)| {
+                (power + lufs_to_power(Self::bin_lufs(bin)) * *count as f64, total + count)
+            });
+        (count > 0).then(|| power_to_lufs(power / count as f64))
+    }
+
+    /// 经过绝对门限和相对门限后的响度
+    fn integrated(&self, relative_gate: f64) -> Option<f64> {
+        let gate = self.gated_mean(ABSOLUTE_GATE)? + relative_gate;
+        self.gated_mean(gate)
+    }
+
+    /// 经过相对门限后第 10 到第 95 百分位之间的差值
+    fn range(&self) -> Option<f64> {
+        let gate = self.gated_mean(ABSOLUTE_GATE)? + RANGE_RELATIVE_GATE;
+        let gated: Vec<(usize, u64)> = self
+            .counts
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(bin, count)| *count > 0 && Self::bin_lufs(*bin) >= gate)
+            .collect();
+        let total: u64 = gated.iter().map(|(_, count)| count).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let percentile = |p: f64| {
+            let target = ((total - 1) as f64 * p).round() as u64;
+            let mut seen = 0;
+            for (bin, count) in &gated {
+                seen += count;
+                if seen > target {
+                    return Self::bin_lufs(*bin);
+                }
+            }
+            Self::bin_lufs(gated[gated.len() - 1].0)
+        };
+        Some(percentile(0.95) - percentile(0.10))
+    }
+}
+
+/// 真峰值测量，采样率低于 96kHz 时 4 倍过采样，低于 192kHz 时 2 倍过采样
+struct TruePeak {
+    factor: usize,
+    coeffs: Vec<f32>,
+    /// 各声道最近的输入采样
+    history: Vec<VecDeque<f32>>,
+    peak: f32,
+}
+
+impl TruePeak {
+    fn new(sample_rate: u32, channels: usize) -> Self {
+        let factor = match sample_rate {
+            0..96000 => 4,
+            96000..192000 => 2,
+            _ => 1,
+        };
+
+        // 截止频率为原始奈奎斯特频率的 Hann 窗 sinc 插值滤波器
+        let len = factor * TRUE_PEAK_TAPS;
+        let center = (len - 1) as f64 / 2.0;
+        let mut coeffs: Vec<f32> = (0..len)
+            .map(|n| {
+                let x = (n as f64 - center) / factor as f64;
+                let sinc = if x == 0.0 { 1.0 } else { (PI * x).sin() / (PI * x) };
+                let window = 0.5 - 0.5 * (2.0 * PI * n as f64 / (len - 1) as f64).cos();
+                (sinc * window) as f32
+            })
+            .collect();
+        let sum: f32 = coeffs.iter().sum();
+        coeffs.iter_mut().for_each(|c| *c *= factor as f32 / sum);
+
+        Self {
+            factor,
+            coeffs,
+            history: vec![VecDeque::from(vec![0.0; TRUE_PEAK_TAPS]); channels],
+            peak: 0.0,
+        }
+    }
+
+    fn reset(&mut self) {
+        for history in &mut self.history {
+            history.iter_mut().for_each(|sample| *sample = 0.0);
+        }
+        self.peak = 0.0;
+    }
+
+    fn process(&mut self, channel: usize, sample: f32) {
+        self.peak = self.peak.max(sample.abs());
+        if self.factor == 1 {
+            return;
+        }
+
+        let history = &mut self.history[channel];
+        history.pop_back();
+        history.push_front(sample);
+        for phase in 0..self.factor {
+            let value: f32 = history
+                .iter()
+                .enumerate()
+                .map(|(k, x)| self.coeffs[phase + k * self.factor] * x)
+                .sum();
+            self.peak = self.peak.max(value.abs());
+        }
+    }
+}
+
+/// 响度测量结果
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct LoudnessStats {
+    /// 积分响度(LUFS)，静音时为空
+    pub integrated: Option<f64>,
+    /// 响度范围(LU)
+    pub range: Option<f64>,
+    /// 真峰值(dBTP)
+    pub true_peak: Option<f64>,
+}
+
+impl LoudnessStats {
+    /// 导出为 JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+/// BS.1770 响度计
+pub struct LoudnessMeter {
+    filters: Vec<[KFilter; 2]>,
+    /// 各声道权重，环绕声道为 1.41，LFE 不计入
+    weights: Vec<f64>,
+    sub_block_frames: usize,
+    sub_block_energy: f64,
+    sub_block_position: usize,
+    /// 最近 3 秒内各子块的能量
+    sub_blocks: VecDeque<f64>,
+    momentary: Histogram,
+    short_term: Histogram,
+    /// 积分响度，每个 400ms 门限块结束时更新
+    integrated: Option<f64>,
+    true_peak: Option<TruePeak>,
+}
+
+impl LoudnessMeter {
+    /// 创建响度计，真峰值测量开销较大，只在需要时启用
+    pub fn new(sample_rate: u32, channels: usize, true_peak: bool) -> Self {
+        let channels = channels.max(1);
+        let weights = (0..channels)
+            .map(|c| match (channels, c) {
+                (6, 3) => 0.0,
+                (6, 4..) | (5, 3..) => 1.41,
+                _ => 1.0,
+            })
+            .collect();
+
+        Self {
+            filters: vec![KFilter::k_weighting(sample_rate); channels],
+            weights,
+            sub_block_frames: (SUB_BLOCK_MS * sample_rate as u64 / 1000).max(1) as usize,
+            sub_block_energy: 0.0,
+            sub_block_position: 0,
+            sub_blocks: VecDeque::with_capacity(SHORT_TERM_SUB_BLOCKS + 1),
+            momentary: Histogram::new(),
+            short_term: Histogram::new(),
+            integrated: None,
+            true_peak: true_peak.then(|| TruePeak::new(sample_rate, channels)),
+        }
+    }
+
+    /// 清除测量结果，开始新的测量
+    pub fn reset(&mut self) {
+        for filter in self.filters.iter_mut().flatten() {
+            filter.z = [0.0; 2];
+        }
+        self.sub_block_energy = 0.0;
+        self.sub_block_position = 0;
+        self.sub_blocks.clear();
+        self.momentary.clear();
+        self.short_term.clear();
+        self.integrated = None;
+        if let Some(true_peak) = self.true_peak.as_mut() {
+            true_peak.reset();
+        }
+    }
+
+    /// 测量交错格式的 f32 音频
+    pub fn process(&mut self, samples: &[f32]) {
+        let channels = self.filters.len();
+        for frame in samples.chunks_exact(channels) {
+            for (c, &sample) in frame.iter().enumerate() {
+                let [shelf, high_pass] = &mut self.filters[c];
+                let weighted = high_pass.process(shelf.process(sample as f64));
+                self.sub_block_energy += self.weights[c] * weighted * weighted;
+                if let Some(true_peak) = self.true_peak.as_mut() {
+                    true_peak.process(c, sample);
+                }
+            }
+
+            self.sub_block_position += 1;
+            if self.sub_block_position == self.sub_block_frames {
+                self.finish_sub_block();
+            }
+        }
+    }
+
+    /// 积分响度(LUFS)
+    pub fn integrated(&self) -> Option<f64> {
+        self.integrated
+    }
+
+    /// 响度范围(LU)
+    pub fn range(&self) -> Option<f64> {
+        self.short_term.range()
+    }
+
+    /// 真峰值(dBTP)
+    pub fn true_peak(&self) -> Option<f64> {
+        self.true_peak
+            .as_ref()
+            .filter(|true_peak| true_peak.peak > 0.0)
+            .map(|true_peak| 20.0 * (true_peak.peak as f64).log10())
+    }
+
+    /// 测量结果
+    pub fn stats(&self) -> LoudnessStats {
+        LoudnessStats {
+            integrated: self.integrated(),
+            range: self.range(),
+            true_peak: self.true_peak(),
+        }
+    }
+
+    fn finish_sub_block(&mut self) {
+        self.sub_blocks.push_back(self.sub_block_energy);
+        if self.sub_blocks.len() > SHORT_TERM_SUB_BLOCKS {
+            self.sub_blocks.pop_front();
+        }
+        self.sub_block_energy = 0.0;
+        self.sub_block_position = 0;
+
+        let mean_lufs = |blocks: usize| {
+            let energy: f64 = self.sub_blocks.iter().rev().take(blocks).sum();
+            power_to_lufs(energy / (blocks * self.sub_block_frames) as f64)
+        };
+        if self.sub_blocks.len() >= MOMENTARY_SUB_BLOCKS {
+            let lufs = mean_lufs(MOMENTARY_SUB_BLOCKS);
+            self.momentary.add(lufs);
+            self.integrated = self.momentary.integrated(INTEGRATED_RELATIVE_GATE);
+        }
+        if self.sub_blocks.len() >= SHORT_TERM_SUB_BLOCKS {
+            let lufs = mean_lufs(SHORT_TERM_SUB_BLOCKS);
+            self.short_term.add(lufs);
+        }
+    }
+}
+
+/// 响度归一化控制，在播放器和响度归一化处理器之间共享
+pub struct LoudnessControl {
+    /// 目标响度(LUFS，f32 位模式)
+    target: AtomicU32,
+    /// 当前轨道是否已应用回放增益，已应用时不再归一化
+    tagged: AtomicBool,
+    /// 当前测得的积分响度(LUFS，f32 位模式)，未知时为 NaN
+    loudness: AtomicU32,
+    /// 当前应用的归一化增益(dB，f32 位模式)
+    gain: AtomicU32,
+}
+
+impl LoudnessControl {
+    /// 创建响度归一化控制
+    pub fn new() -> Self {
+        Self {
+            target: AtomicU32::new(DEFAULT_TARGET_LUFS.to_bits()),
+            tagged: AtomicBool::new(false),
+            loudness: AtomicU32::new(f32::NAN.to_bits()),
+            gain: AtomicU32::new(0f32.to_bits()),
+        }
+    }
+
+    /// 目标响度(LUFS)
+    pub fn target_lufs(&self) -> f32 {
+        f32::from_bits(self.target.load(Ordering::Relaxed))
+    }
+
+    /// 设置目标响度(LUFS)
+    pub fn set_target_lufs(&self, target_lufs: f32) {
+        self.target.store(target_lufs.to_bits(), Ordering::Relaxed);
+    }
+
+    /// 当前轨道是否已应用回放增益
+    pub fn set_track_tagged(&self, tagged: bool) {
+        self.tagged.store(tagged, Ordering::Relaxed);
+    }
+
+    /// 当前测得的积分响度(LUFS)
+    pub fn loudness(&self) -> Option<f32> {
+        Some(f32::from_bits(self.loudness.load(Ordering::Relaxed))).filter(|lufs| !lufs.is_nan())
+    }
+
+    /// 当前应用的归一化增益(dB)
+    pub fn gain_db(&self) -> f32 {
+        f32::from_bits(self.gain.load(Ordering::Relaxed))
+    }
+}
+
+impl Default for LoudnessControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 响度归一化处理器，边播放边测量积分响度，并将增益缓慢移向目标响度
+pub struct LoudnessNormalizer {
+    control: Arc<LoudnessControl>,
+    meter: Option<LoudnessMeter>,
+    gain_db: f32,
+    ramp: GainRamp,
+    limiter: Limiter,
+    sample_rate: u32,
+}
+
+impl LoudnessNormalizer {
+    /// 创建响度归一化处理器
+    pub fn new(control: Arc<LoudnessControl>) -> Self {
+        Self {
+            control,
+            meter: None,
+            gain_db: 0.0,
+            ramp: GainRamp::new(1.0),
+            limiter: Limiter::new(0),
+            sample_rate: 0,
+        }
+    }
+}
+
+impl AudioProcessor for LoudnessNormalizer {
+    fn name(&self) -> &str {
+        "loudness"
+    }
+
+    fn configure(&mut self, sample_rate: u32, channels: usize) {
+        self.sample_rate = sample_rate;
+        self.meter = Some(LoudnessMeter::new(sample_rate, channels, false));
+        self.limiter = Limiter::new(sample_rate);
+    }
+
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        let Some(meter) = self.meter.as_mut() else {
+            return;
+        };
+        meter.process(samples);
+
+        // 测量结果出来之前保持当前增益
+        let loudness = meter.integrated().map(|lufs| lufs as f32);
+        let target = if self.control.tagged.load(Ordering::Relaxed) {
+            0.0
+        } else {
+            loudness.map_or(self.gain_db, |lufs| {
+                (self.control.target_lufs() - lufs).clamp(-MAX_CUT_DB, MAX_BOOST_DB)
+            })
+        };
+
+        let frames = samples.len() / channels.max(1);
+        let max_step = GAIN_SLEW_DB_PER_SEC * frames as f32 / self.sample_rate.max(1) as f32;
+        self.gain_db += (target - self.gain_db).clamp(-max_step, max_step);
+        self.ramp.set_target(db_to_gain(self.gain_db), frames);
+        self.ramp.apply(samples, channels);
+
+        if self.ramp.peak() > 1.0 || self.limiter.is_active() {
+            self.limiter.process(samples, channels);
+        }
+
+        self.control
+            .loudness
+            .store(loudness.unwrap_or(f32::NAN).to_bits(), Ordering::Relaxed);
+        self.control.gain.store(self.gain_db.to_bits(), Ordering::Relaxed);
+    }
+
+    fn reset(&mut self) {
+        self.ramp = GainRamp::new(db_to_gain(self.gain_db));
+        self.limiter.reset();
+    }
+
+    fn start_track(&mut self) {
+        if let Some(meter) = self.meter.as_mut() {
+            meter.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 48000;
+
+    /// 各声道相同的正弦波，幅度单位为 dBFS
+    fn sine(frequency: f64, dbfs: f64, channels: usize, millis: u64) -> Vec<f32> {
+        let amplitude = 10f64.powf(dbfs / 20.0);
+        let frames = (RATE as u64 * millis / 1000) as usize;
+        (0..frames)
+            .flat_map(|i| {
+                let sample = amplitude * (2.0 * PI * frequency * i as f64 / RATE as f64).sin();
+                std::iter::repeat_n(sample as f32, channels)
+            })
+            .collect()
+    }
+
+    fn measure(samples: &[f32], channels: usize, true_peak: bool) -> LoudnessMeter {
+        let mut meter = LoudnessMeter::new(RATE, channels, true_peak);
+        meter.process(samples);
+        meter
+    }
+
+    #[test]
+    fn mono_sine_at_minus_20_dbfs() {
+        let meter = measure(&sine(1000.0, -20.0, 1, 5000), 1, false);
+        let integrated = meter.integrated().unwrap();
+        assert!((integrated + 23.0).abs() < 0.2, "integrated {integrated}");
+        assert!(meter.range().unwrap() < 0.2);
+    }
+
+    #[test]
+    fn stereo_sums_channel_power() {
+        let integrated = measure(&sine(1000.0, -20.0, 2, 5000), 2, false).integrated().unwrap();
+        assert!((integrated + 20.0).abs() < 0.2, "integrated {integrated}");
+    }
+
+    #[test]
+    fn lfe_is_not_counted() {
+        let mut samples = sine(1000.0, -20.0, 6, 2000);
+        for frame in samples.chunks_exact_mut(6) {
+            for (c, sample) in frame.iter_mut().enumerate() {
+                if c != 3 {
+                    *sample = 0.0;
+                }
+            }
+        }
+        assert_eq!(measure(&samples, 6, false).integrated(), None);
+    }
+
+    #[test]
+    fn silence_has_no_loudness() {
+        let stats = measure(&vec![0.0; RATE as usize * 2], 2, true).stats();
+        assert_eq!(stats.integrated, None);
+        assert_eq!(stats.range, None);
+        assert_eq!(stats.true_peak, None);
+    }
+
+    #[test]
+    fn integrated_updates_after_first_gating_block() {
+        let mut meter = LoudnessMeter::new(RATE, 1, false);
+        meter.process(&sine(1000.0, -20.0, 1, 399));
+        assert_eq!(meter.integrated(), None);
+        meter.process(&sine(1000.0, -20.0, 1, 1));
+        assert!(meter.integrated().is_some());
+
+        meter.reset();
+        assert_eq!(meter.integrated(), None);
+    }
+
+    #[test]
+    fn quiet_blocks_are_gated() {
+        let mut samples = sine(1000.0, -20.0, 1, 3000);
+        samples.extend(sine(1000.0, -50.0, 1, 3000));
+        let integrated = measure(&samples, 1, false).integrated().unwrap();
+        assert!((integrated + 23.0).abs() < 0.5, "integrated {integrated}");
+    }
+
+    #[test]
+    fn true_peak_between_samples() {
+        // 四分之一采样率、相位 45° 的正弦波，采样点只有峰值的 0.707
+        let samples: Vec<f32> = (0..RATE as usize)
+            .map(|i| (PI / 2.0 * i as f64 + PI / 4.0).sin() as f32 * 0.5)
+            .collect();
+        let true_peak = measure(&samples, 1, true).true_peak().unwrap();
+        assert!((true_peak - 20.0 * 0.5f64.log10()).abs() < 0.5, "true peak {true_peak}");
+    }
+
+    #[test]
+    fn normalizer_moves_gain_toward_target() {
+        let control = Arc::new(LoudnessControl::new());
+        control.set_target_lufs(-18.0);
+        let mut normalizer = LoudnessNormalizer::new(control.clone());
+        normalizer.configure(RATE, 1);
+
+        let mut samples = sine(1000.0, -20.0, 1, 2000);
+        for chunk in samples.chunks_mut(480) {
+            normalizer.process(chunk, 1);
+        }
+        let loudness = control.loudness().unwrap();
+        assert!((loudness + 23.0).abs() < 0.2, "loudness {loudness}");
+        // 测得响度后增益每秒最多变化 3dB
+        let gain_db = control.gain_db();
+        assert!((gain_db - GAIN_SLEW_DB_PER_SEC * 1.6).abs() < 0.1, "gain {gain_db}");
+
+        control.set_track_tagged(true);
+        for chunk in samples.chunks_mut(480) {
+            normalizer.process(chunk, 1);
+        }
+        assert!(control.gain_db().abs() < 1e-3);
+    }
+}
@@ -1,23 +1,41 @@
 //! 音频处理模块
 //!
-//! 提供音频输出、重采样、播放器和类型定义功能
+//! 提供音频输出、输出设备枚举、声道布局转换、抖动、重采样、变速、交叉淡化、增益渐变、回放增益、响度归一化、处理链、均衡器、立体声、播放器和类型定义功能
 
+pub mod channels;
+pub mod crossfade;
+pub mod device;
+pub mod dither;
+pub mod dsp;
+pub mod equalizer;
+pub mod gain;
+pub mod loudness;
 pub mod output;
+pub mod replaygain;
 pub mod resampler;
+pub mod stereo;
+pub mod stretch;
 pub mod types;
 
 use symphonia::core::audio::SignalSpec;
 use symphonia::core::units::Duration;
+use crate::audio::output::OutputControl;
 use crate::audio::types::Result;
+use std::sync::Arc;
 
 /// 创建音频输出设备
 ///
 /// # 参数
 /// * `spec` - 音频信号规格
 /// * `duration` - 音频持续时间
+/// * `control` - 输出控制，用于暂停和恢复
 ///
 /// # 返回值
 /// * `Result<AudioOutput>` - 音频输出设备或错误
-pub fn create_audio_output(spec: SignalSpec, duration: Duration) -> Result<crate::audio::output::AudioOutput> {
-    crate::audio::output::create_audio_output(spec, duration)
+pub fn create_audio_output(
+    spec: SignalSpec,
+    duration: Duration,
+    control: Arc<OutputControl>,
+) -> Result<crate::audio::output::AudioOutput> {
+    crate::audio::output::create_audio_output(spec, duration, control)
 }
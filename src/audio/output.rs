@@ -2,72 +2,576 @@
 //!
 //! 提供基于CPAL的跨平台音频输出功能
 
+use crate::audio::channels::{ChannelMatrix, choose_channels};
+use crate::audio::device::find_output_device;
+use crate::audio::dither::Dither;
+use crate::audio::dsp::{ChainController, ProcessorChain};
+use crate::audio::gain::{VolumeControl, VolumeProcessor};
 use crate::audio::resampler::Resampler;
+use crate::audio::stretch::{MAX_SPEED, MIN_SPEED, SpeedMode, TimeStretcher};
 use crate::audio::types::{AudioOutputError, Result};
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::traits::{DeviceTrait, StreamTrait};
 use rb::*;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration as StdDuration, Instant};
 use symphonia::core::{
     audio::{AudioBufferRef, SampleBuffer, SignalSpec},
-    conv::IntoSample,
     units::Duration,
 };
 
+/// 没有待通知的轨道起点
+const NO_TRACK_START: u64 = u64::MAX;
+
+/// 重采样变速时输入采样率的取整步长(Hz)，使输入和输出采样率保持较大的公约数，避免 FFT 过长带来的延迟
+const SPEED_RATE_STEP: u32 = 100;
+
+/// 设备不支持音源采样率时优先考虑的常用采样率
+const COMMON_SAMPLE_RATES: [u32; 6] = [44100, 48000, 88200, 96000, 176400, 192000];
+
+/// 音频回调超过该时长(毫秒)没有读取环形缓冲区时认为输出流已失效
+const STALL_TIMEOUT_MS: u64 = 2000;
+
+/// 写入线程单次等待的时长(毫秒)，音频回调不加锁通知，可能错过通知
+const WAIT_SLICE_MS: u64 = 10;
+
+/// 输出流失效后重建的尝试次数
+const RECOVERY_ATTEMPTS: u32 = 3;
+
+/// 重建失败后再次尝试前的等待时长(毫秒)，等待系统切换默认设备
+const RECOVERY_INTERVAL_MS: u64 = 500;
+
+/// 输出控制，在播放器、播放线程和音频回调之间共享
+pub struct OutputControl {
+    /// 音量和暂停淡入淡出控制，由处理链中的音量处理器读取
+    volume: Arc<VolumeControl>,
+    /// 音频处理链的控制端
+    chain: Mutex<ChainController>,
+    /// 没有输出流时保留的音频处理链，输出流重建时交给新的音频回调，因此不会丢失
+    idle_chain: Mutex<Option<ProcessorChain>>,
+    /// 输出 16 位整数时是否启用噪声整形
+    noise_shaping: AtomicBool,
+    /// 选择的音频后端名称，未选择时使用默认后端
+    host: Mutex<Option<String>>,
+    /// 选择的输出设备名称，未选择时使用默认设备
+    device: Mutex<Option<String>>,
+    /// 选择的设备不可用时使用的备用设备名称
+    fallback_device: Mutex<Option<String>>,
+    /// 每次选择音频后端或输出设备时递增，旧设备上的输出流随之重建
+    device_generation: AtomicU64,
+    lock: Mutex<()>,
+    /// 环形缓冲区有空位或暂停状态变化时通知写入线程
+    changed: Condvar,
+}
+
+impl OutputControl {
+    /// 创建输出控制
+    pub fn new() -> Self {
+        let volume = Arc::new(VolumeControl::new());
+        let (chain, controller) = ProcessorChain::new(Box::new(VolumeProcessor::new(Arc::clone(&volume))));
+        Self {
+            volume,
+            chain: Mutex::new(controller),
+            idle_chain: Mutex::new(Some(chain)),
+            noise_shaping: AtomicBool::new(false),
+            host: Mutex::new(None),
+            device: Mutex::new(None),
+            fallback_device: Mutex::new(None),
+            device_generation: AtomicU64::new(0),
+            lock: Mutex::new(()),
+            changed: Condvar::new(),
+        }
+    }
+
+    /// 是否暂停
+    pub fn is_paused(&self) -> bool {
+        self.volume.is_paused()
+    }
+
+    /// 设置暂停状态，暂停时音频回调淡出后输出静音且不再读取环形缓冲区
+    pub fn set_paused(&self, paused: bool) {
+        self.volume.set_paused(paused);
+        self.notify();
+    }
+
+    /// 目标输出增益
+    pub fn volume(&self) -> f32 {
+        self.volume.volume()
+    }
+
+    /// 在指定时长(毫秒)内渐变到目标输出增益
+    pub fn set_volume(&self, volume: f32, ramp_ms: u64) {
+        self.volume.set_volume(volume, ramp_ms);
+    }
+
+    /// 淡入淡出时长(毫秒)
+    pub fn fade_duration(&self) -> u64 {
+        self.volume.fade_duration()
+    }
+
+    /// 设置淡入淡出时长(毫秒)
+    pub fn set_fade_duration(&self, fade_ms: u64) {
+        self.volume.set_fade_duration(fade_ms);
+    }
+
+    /// 输出 16 位整数时是否启用噪声整形
+    pub fn is_noise_shaping(&self) -> bool {
+        self.noise_shaping.load(Ordering::Relaxed)
+    }
+
+    /// 设置输出 16 位整数时是否启用噪声整形
+    pub fn set_noise_shaping(&self, noise_shaping: bool) {
+        self.noise_shaping.store(noise_shaping, Ordering::Relaxed);
+    }
+
+    /// 选择的音频后端名称
+    pub fn output_host(&self) -> Option<String> {
+        self.host.lock().unwrap().clone()
+    }
+
+    /// 选择音频后端，设备名称属于原后端，因此同时恢复为默认设备并清除备用设备
+    pub fn set_output_host(&self, host: Option<String>) {
+        *self.host.lock().unwrap() = host;
+        *self.device.lock().unwrap() = None;
+        *self.fallback_device.lock().unwrap() = None;
+        self.device_generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// 选择的输出设备名称
+    pub fn output_device(&self) -> Option<String> {
+        self.device.lock().unwrap().clone()
+    }
+
+    /// 选择输出设备，正在播放时切换到新设备
+    pub fn set_output_device(&self, device: Option<String>) {
+        *self.device.lock().unwrap() = device;
+        self.device_generation.fetch_add(1, Ordering::Release);
+    }
+
+    /// 备用设备名称
+    pub fn fallback_device(&self) -> Option<String> {
+        self.fallback_device.lock().unwrap().clone()
+    }
+
+    /// 选择的设备不可用时使用的备用设备，None 表示直接使用默认设备
+    pub fn set_fallback_device(&self, device: Option<String>) {
+        *self.fallback_device.lock().unwrap() = device;
+    }
+
+    /// 音频处理链的控制端，修改由音频回调在下一次处理前应用
+    pub fn chain(&self) -> MutexGuard<'_, ChainController> {
+        self.chain.lock().unwrap()
+    }
+
+    /// 暂停后等待音频回调淡出到静音，超时或恢复时返回
+    pub fn wait_until_silent(&self) {
+        // 设备停止回调时不会再淡出，超时后直接返回
+        let timeout = StdDuration::from_millis(self.fade_duration() + 100);
+        self.wait_while(timeout, || self.is_paused() && !self.volume.is_silent());
+    }
+
+    /// 分段等待直到条件不成立或超时，返回是否超时
+    fn wait_while(&self, timeout: StdDuration, condition: impl Fn() -> bool) -> bool {
+        let start = Instant::now();
+        let mut guard = self.lock.lock().unwrap();
+        while condition() {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return true;
+            }
+            let slice = (timeout - elapsed).min(StdDuration::from_millis(WAIT_SLICE_MS));
+            guard = self.changed.wait_timeout(guard, slice).unwrap().0;
+        }
+        false
+    }
+
+    fn notify(&self) {
+        let _guard = self.lock.lock().unwrap();
+        self.changed.notify_all();
+    }
+
+    /// 在音频回调中唤醒写入线程，不加锁以免阻塞
+    fn notify_from_callback(&self) {
+        self.changed.notify_all();
+    }
+
+    /// 等待环形缓冲区出现空位，暂停或输出流失效时立即返回
+    fn wait_for_space(&self, ring_buf: &SpscRb<f32>, failed: &AtomicBool) {
+        let timeout = StdDuration::from_millis(STALL_TIMEOUT_MS);
+        let stalled = self.wait_while(timeout, || {
+            ring_buf.is_full() && !self.is_paused() && !failed.load(Ordering::Acquire)
+        });
+        // 部分后端在设备拔出时只报告后端错误或直接停止回调，长时间没有读取时同样视为失效
+        if stalled {
+            eprintln!("Output stream stalled");
+            failed.store(true, Ordering::Release);
+        }
+    }
+}
+
+impl Default for OutputControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 音频回调，从环形缓冲区读取音频并经过处理链输出
+struct OutputCallback {
+    consumer: rb::Consumer<f32>,
+    control: Arc<OutputControl>,
+    device_latency: Arc<AtomicU64>,
+    channels: usize,
+    sample_rate: u32,
+    /// 整数格式输出时的 f32 中间缓冲
+    scratch: Vec<f32>,
+    /// u16 格式输出时的 i16 中间缓冲
+    quantized: Vec<i16>,
+    dither: Dither,
+    /// 音频处理链，输出流释放时交还输出控制
+    chain: Option<ProcessorChain>,
+    /// 已从环形缓冲区读取的采样数
+    read: u64,
+    /// 新轨道第一个采样在环形缓冲区中的位置，播放到该位置时通知处理链
+    track_start: Arc<AtomicU64>,
+    /// 定位前写入的采样位置，读取到该位置之前的采样直接丢弃
+    discard_until: Arc<AtomicU64>,
+}
+
+impl OutputCallback {
+    fn new(
+        consumer: rb::Consumer<f32>,
+        control: Arc<OutputControl>,
+        device_latency: Arc<AtomicU64>,
+        channels: usize,
+        sample_rate: u32,
+        track_start: Arc<AtomicU64>,
+        discard_until: Arc<AtomicU64>,
+    ) -> Self {
+        // 新建输出流时清除处理链状态，音量处理器从静音淡入
+        let mut chain = control.idle_chain.lock().unwrap().take();
+        if let Some(chain) = chain.as_mut() {
+            chain.reset();
+        }
+        Self {
+            consumer,
+            control,
+            device_latency,
+            channels,
+            sample_rate,
+            scratch: Vec::new(),
+            quantized: Vec::new(),
+            dither: Dither::new(channels),
+            chain,
+            read: 0,
+            track_start,
+            discard_until,
+        }
+    }
+
+    /// 以 f32 渲染后加入抖动量化为 i16
+    fn render_i16(&mut self, data: &mut [i16], info: &cpal::OutputCallbackInfo) {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.resize(data.len(), 0.0);
+        self.render(&mut scratch, info);
+        self.dither.quantize(&scratch, data, self.control.is_noise_shaping());
+        self.scratch = scratch;
+    }
+
+    /// 以 i16 渲染后偏移为 u16
+    fn render_u16(&mut self, data: &mut [u16], info: &cpal::OutputCallbackInfo) {
+        let mut quantized = std::mem::take(&mut self.quantized);
+        quantized.resize(data.len(), 0);
+        self.render_i16(&mut quantized, info);
+        for (output, sample) in data.iter_mut().zip(quantized.iter()) {
+            *output = (*sample as i32 + 32768) as u16;
+        }
+        self.quantized = quantized;
+    }
+
+    fn render(&mut self, data: &mut [f32], info: &cpal::OutputCallbackInfo) {
+        let timestamp = info.timestamp();
+        if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+            self.device_latency.store(latency.as_micros() as u64, Ordering::Relaxed);
+        }
+
+        // 旧输出流的音频回调尚未释放时从输出控制接管处理链，接管前输出静音且不读取缓冲区
+        if self.chain.is_none()
+            && let Ok(mut idle_chain) = self.control.idle_chain.try_lock()
+        {
+            self.chain = idle_chain.take();
+            if let Some(chain) = self.chain.as_mut() {
+                chain.reset();
+            }
+        }
+        let Some(chain) = self.chain.as_mut() else {
+            data.iter_mut().for_each(|s| *s = 0.0);
+            return;
+        };
+
+        // 淡出完成后保留缓冲区中的音频，恢复后继续播放
+        if self.control.is_paused() && self.control.volume.is_silent() {
+            data.iter_mut().for_each(|s| *s = 0.0);
+            return;
+        }
+
+        // 定位后丢弃定位前写入的音频
+        let discard_until = self.discard_until.load(Ordering::Acquire);
+        let mut skipped = 0;
+        if self.read < discard_until {
+            skipped = self.consumer.skip((discard_until - self.read) as usize).unwrap_or(0);
+            self.read += skipped as u64;
+        }
+
+        let written = self.consumer.read(data).unwrap_or(0);
+        data[written..].iter_mut().for_each(|s| *s = 0.0);
+
+        // 本次读取包含新轨道的起点时，在起点处拆分处理
+        let track_start = self.track_start.load(Ordering::Acquire);
+        let split = (track_start != NO_TRACK_START && track_start <= self.read + written as u64).then(|| {
+            let offset = track_start.saturating_sub(self.read) as usize;
+            offset - offset % self.channels.max(1)
+        });
+        self.read += written as u64;
+
+        chain.apply_commands();
+        chain.prepare(self.sample_rate, self.channels);
+        match split {
+            Some(offset) => {
+                let (before, after) = data.split_at_mut(offset);
+                chain.process(before, self.channels);
+                chain.start_track();
+                chain.process(after, self.channels);
+                // 期间写入线程已标记更新的起点时保留
+                let _ = self.track_start.compare_exchange(
+                    track_start,
+                    NO_TRACK_START,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                );
+            }
+            None => chain.process(data, self.channels),
+        }
+
+        // 环形缓冲区有空位或刚淡出到静音时唤醒写入线程
+        if written + skipped > 0 || (self.control.is_paused() && self.control.volume.is_silent()) {
+            self.control.notify_from_callback();
+        }
+    }
+}
+
+impl Drop for OutputCallback {
+    fn drop(&mut self) {
+        // 输出流释放后处理链交还输出控制，由下一个输出流继续使用
+        if let Some(chain) = self.chain.take() {
+            *self.control.idle_chain.lock().unwrap() = Some(chain);
+        }
+    }
+}
 
 /// 音频输出实现
 pub struct AudioOutput {
+    ring_buf: SpscRb<f32>,
     ring_buf_producer: rb::Producer<f32>,
     sample_buf: SampleBuffer<f32>,
     stream: cpal::Stream,
     resampler: Option<Resampler<f32>>,
-    volume: f32,
+    spec: SignalSpec,
+    duration: Duration,
+    /// 设备输出采样率
+    sample_rate: u32,
+    /// 设备输出声道数
+    channels: usize,
+    /// 音源与设备声道数不同时的下混或上混矩阵
+    matrix: Option<ChannelMatrix>,
+    /// 声道转换后的采样
+    mix_buf: Vec<f32>,
+    /// 设备延迟(微秒)，由音频回调更新
+    device_latency: Arc<AtomicU64>,
+    /// 播放速度
+    speed: f32,
+    speed_mode: SpeedMode,
+    /// 保持音调变速时使用的时间伸缩器
+    stretcher: Option<TimeStretcher>,
+    control: Arc<OutputControl>,
+    /// 暂停时尚未写入环形缓冲区的采样
+    pending: Vec<f32>,
+    /// 创建时的输出设备选择
+    device_generation: u64,
+    /// 输出流是否已失效，由错误回调或写入线程设置
+    failed: Arc<AtomicBool>,
+    /// 已写入环形缓冲区的采样数
+    written: u64,
+    /// 新轨道第一个采样在环形缓冲区中的位置，由音频回调读取
+    track_start: Arc<AtomicU64>,
+    /// 定位前写入的采样位置，由音频回调丢弃
+    discard_until: Arc<AtomicU64>,
 }
 
 impl AudioOutput {
     /// 创建音频输出设备
-    pub fn new(spec: SignalSpec, duration: Duration) -> Result<Self> {
-        let host = cpal::default_host();
-        let device = match host.default_output_device() {
-            Some(device) => device,
-            _ => return Err(AudioOutputError::OpenStreamError),
-        };
+    pub fn new(spec: SignalSpec, duration: Duration, control: Arc<OutputControl>) -> Result<Self> {
+        // 先记录设备选择，打开期间再次选择设备时下一次写入会重建输出流
+        let device_generation = control.device_generation.load(Ordering::Acquire);
 
+        // 选择的设备不可用时依次使用备用设备和默认后端的默认设备
+        let host = control.output_host();
+        let selected = control.output_device();
+        let mut candidates = vec![(host.clone(), selected.clone())];
+        if let Some(fallback) = control.fallback_device() {
+            candidates.push((host.clone(), Some(fallback)));
+        }
+        candidates.push((None, None));
+        candidates.dedup();
+
+        let mut last_error = AudioOutputError::DeviceNotFound;
+        for (i, (host, name)) in candidates.iter().enumerate() {
+            let result = find_output_device(host.as_deref(), name.as_deref())
+                .and_then(|device| Self::open(spec, duration, &device, Arc::clone(&control)));
+            match result {
+                Ok(mut output) => {
+                    if i > 0 {
+                        eprintln!("Output device {:?} not available, using {:?} on host {:?}", selected, name, host);
+                    }
+                    output.device_generation = device_generation;
+                    return Ok(output);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// 在指定设备上打开输出流
+    fn open(
+        spec: SignalSpec,
+        duration: Duration,
+        device: &cpal::Device,
+        control: Arc<OutputControl>,
+    ) -> Result<Self> {
         let config = match device.default_output_config() {
             Ok(config) => config,
             Err(_) => return Err(AudioOutputError::OpenStreamError),
         };
 
-        // 优先使用 f32 格式，如果不支持则尝试其他格式
-        if config.sample_format() == cpal::SampleFormat::F32 {
-            Self::create_impl(spec, duration, &device)
-        } else {
-            // 如果设备不支持 f32，尝试使用设备默认格式
-            Self::create_with_device_format(spec, duration, &device, &config)
-        }
+        let (stream_config, sample_format) = Self::negotiate_config(device, &config, spec);
+
+        Self::create_with_device_format(spec, duration, stream_config, sample_format, device, control)
     }
 
-    /// 使用 f32 格式创建音频输出实现
-    fn create_impl(spec: SignalSpec, duration: Duration, device: &cpal::Device) -> Result<Self> {
-        let num_channels = spec.channels.count();
+    /// 选择设备支持的声道数、采样率和采样格式，无法获取支持的配置时使用设备默认配置
+    fn negotiate_config(
+        device: &cpal::Device,
+        default_config: &cpal::SupportedStreamConfig,
+        spec: SignalSpec,
+    ) -> (cpal::StreamConfig, cpal::SampleFormat) {
+        let supported: Vec<cpal::SupportedStreamConfigRange> = device
+            .supported_output_configs()
+            .map(|configs| configs.collect())
+            .unwrap_or_default();
+
+        let counts: Vec<usize> = supported.iter().map(|config| config.channels() as usize).collect();
+        let channels = choose_channels(&counts, spec.channels.count())
+            .unwrap_or(default_config.channels() as usize);
+
+        let ranges: Vec<(u32, u32)> = supported
+            .iter()
+            .filter(|config| config.channels() as usize == channels)
+            .map(|config| (config.min_sample_rate().0, config.max_sample_rate().0))
+            .collect();
+        let sample_rate = Self::choose_sample_rate(&ranges, spec.rate, default_config.sample_rate().0);
+
+        // 在支持该声道数和采样率的配置中按 f32、i16、u16 的顺序选择格式
+        let formats: Vec<cpal::SampleFormat> = supported
+            .iter()
+            .filter(|config| {
+                config.channels() as usize == channels
+                    && (config.min_sample_rate().0..=config.max_sample_rate().0).contains(&sample_rate)
+            })
+            .map(|config| config.sample_format())
+            .collect();
+        let sample_format = [cpal::SampleFormat::F32, cpal::SampleFormat::I16, cpal::SampleFormat::U16]
+            .into_iter()
+            .find(|format| formats.contains(format))
+            .unwrap_or(default_config.sample_format());
 
         let config = cpal::StreamConfig {
-            channels: num_channels as cpal::ChannelCount,
-            sample_rate: cpal::SampleRate(spec.rate),
+            channels: channels as cpal::ChannelCount,
+            sample_rate: cpal::SampleRate(sample_rate),
             buffer_size: cpal::BufferSize::Default,
         };
+        (config, sample_format)
+    }
+
+    /// 选择输出采样率，设备支持音源采样率时直接使用，否则使用不低于音源的最低可用采样率，再否则使用最高的可用采样率
+    fn choose_sample_rate(ranges: &[(u32, u32)], wanted: u32, default_rate: u32) -> u32 {
+        let supports = |rate: u32| ranges.iter().any(|(min, max)| (*min..=*max).contains(&rate));
+        if supports(wanted) {
+            return wanted;
+        }
+
+        let candidates: Vec<u32> = COMMON_SAMPLE_RATES
+            .iter()
+            .copied()
+            .chain([default_rate])
+            .chain(ranges.iter().flat_map(|(min, max)| [*min, *max]))
+            .filter(|rate| supports(*rate))
+            .collect();
+        candidates
+            .iter()
+            .copied()
+            .filter(|rate| *rate >= wanted)
+            .min()
+            .or_else(|| candidates.iter().copied().max())
+            .unwrap_or(default_rate)
+    }
+
+    /// 按设备采样格式创建音频输出实现，整数格式量化时加入抖动
+    fn create_with_device_format(
+        spec: SignalSpec,
+        duration: Duration,
+        config: cpal::StreamConfig,
+        sample_format: cpal::SampleFormat,
+        device: &cpal::Device,
+        control: Arc<OutputControl>,
+    ) -> Result<Self> {
+        let num_channels = config.channels as usize;
 
         let ring_len = ((200 * config.sample_rate.0 as usize) / 1000) * num_channels;
         let ring_buf = SpscRb::new(ring_len);
         let (ring_buf_producer, ring_buf_consumer) = (ring_buf.producer(), ring_buf.consumer());
 
-        let stream_result = device.build_output_stream(
-            &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let written = ring_buf_consumer.read(data).unwrap_or(0);
-                data[written..].iter_mut().for_each(|s| *s = 0.0);
-            },
-            move |_| {},
+        let device_latency = Arc::new(AtomicU64::new(0));
+        let track_start = Arc::new(AtomicU64::new(NO_TRACK_START));
+        let discard_until = Arc::new(AtomicU64::new(0));
+        let mut callback = OutputCallback::new(
+            ring_buf_consumer,
+            Arc::clone(&control),
+            Arc::clone(&device_latency),
+            num_channels,
+            config.sample_rate.0,
+            Arc::clone(&track_start),
+            Arc::clone(&discard_until),
         );
 
+        let failed = Arc::new(AtomicBool::new(false));
+        let stream_result = match sample_format {
+            cpal::SampleFormat::I16 => device.build_output_stream(
+                &config,
+                move |data: &mut [i16], info: &cpal::OutputCallbackInfo| callback.render_i16(data, info),
+                Self::error_callback(Arc::clone(&failed), Arc::clone(&control)),
+            ),
+            cpal::SampleFormat::U16 => device.build_output_stream(
+                &config,
+                move |data: &mut [u16], info: &cpal::OutputCallbackInfo| callback.render_u16(data, info),
+                Self::error_callback(Arc::clone(&failed), Arc::clone(&control)),
+            ),
+            cpal::SampleFormat::F32 => device.build_output_stream(
+                &config,
+                move |data: &mut [f32], info: &cpal::OutputCallbackInfo| callback.render(data, info),
+                Self::error_callback(Arc::clone(&failed), Arc::clone(&control)),
+            ),
+        };
+
         if let Err(_) = stream_result {
             return Err(AudioOutputError::OpenStreamError);
         }
@@ -78,80 +582,129 @@ impl AudioOutput {
         }
 
         let sample_buf = SampleBuffer::<f32>::new(duration, spec);
-        let resampler = if spec.rate != config.sample_rate.0 {
-            Some(Resampler::new(
-                spec,
-                config.sample_rate.0 as usize,
-                duration,
-            ))
-        } else {
-            None
-        };
+        let resampler = Self::create_resampler(
+            spec,
+            config.sample_rate.0,
+            duration,
+            1.0,
+            SpeedMode::Resample,
+        );
 
         Ok(Self {
+            ring_buf,
             ring_buf_producer,
             sample_buf,
             stream,
             resampler,
-            volume: 1.0,
+            spec,
+            duration,
+            sample_rate: config.sample_rate.0,
+            channels: num_channels,
+            matrix: ChannelMatrix::new(spec.channels, num_channels),
+            mix_buf: Vec::new(),
+            device_latency,
+            speed: 1.0,
+            speed_mode: SpeedMode::Resample,
+            stretcher: None,
+            device_generation: 0,
+            failed,
+            control,
+            pending: Vec::new(),
+            written: 0,
+            track_start,
+            discard_until,
         })
     }
 
-    /// 使用设备默认格式创建音频输出实现
-    fn create_with_device_format(
+    /// 创建重采样器，重采样变速时按速度调整输入采样率
+    fn create_resampler(
         spec: SignalSpec,
+        output_rate: u32,
         duration: Duration,
-        device: &cpal::Device,
-        _device_config: &cpal::SupportedStreamConfig,
-    ) -> Result<Self> {
-        let num_channels = spec.channels.count();
-
-        let config = cpal::StreamConfig {
-            channels: num_channels as cpal::ChannelCount,
-            sample_rate: cpal::SampleRate(spec.rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
-
-        let ring_len = ((200 * config.sample_rate.0 as usize) / 1000) * num_channels;
-        let ring_buf = SpscRb::new(ring_len);
-        let (ring_buf_producer, ring_buf_consumer) = (ring_buf.producer(), ring_buf.consumer());
+        speed: f32,
+        speed_mode: SpeedMode,
+    ) -> Option<Resampler<f32>> {
+        let input_rate = Self::resampler_input_rate(spec, speed, speed_mode);
+        if input_rate != output_rate {
+            Some(Resampler::new(
+                SignalSpec::new(input_rate, spec.channels),
+                output_rate as usize,
+                duration,
+            ))
+        } else {
+            None
+        }
+    }
 
-        let stream_result = device.build_output_stream(
-            &config,
-            move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                let written = ring_buf_consumer.read(data).unwrap_or(0);
-                data[written..].iter_mut().for_each(|s| *s = 0.0);
-            },
-            move |_| {},
-        );
+    /// 重采样器的输入采样率，重采样变速时按速度调整并取整到步长
+    fn resampler_input_rate(spec: SignalSpec, speed: f32, speed_mode: SpeedMode) -> u32 {
+        match speed_mode {
+            SpeedMode::Resample if speed != 1.0 => {
+                let rate = (spec.rate as f32 * speed / SPEED_RATE_STEP as f32).round() as u32;
+                rate.max(1) * SPEED_RATE_STEP
+            }
+            _ => spec.rate,
+        }
+    }
 
-        if let Err(_) = stream_result {
-            return Err(AudioOutputError::OpenStreamError);
+    /// 实际播放速度，重采样变速时输入采样率取整后与设置的速度略有差别
+    fn playback_speed(&self) -> f64 {
+        match self.speed_mode {
+            SpeedMode::Resample => {
+                Self::resampler_input_rate(self.spec, self.speed, self.speed_mode) as f64 / self.spec.rate as f64
+            }
+            SpeedMode::TimeStretch => self.speed as f64,
         }
+    }
 
-        let stream = stream_result.unwrap();
-        if let Err(_) = stream.play() {
-            return Err(AudioOutputError::PlayStreamError);
+    /// 设备错误时标记输出流失效并唤醒写入线程
+    fn error_callback(
+        failed: Arc<AtomicBool>,
+        control: Arc<OutputControl>,
+    ) -> impl FnMut(cpal::StreamError) + Send + 'static {
+        move |error| {
+            eprintln!("Output stream error: {}", error);
+            if matches!(error, cpal::StreamError::DeviceNotAvailable) {
+                failed.store(true, Ordering::Release);
+                control.notify_from_callback();
+            }
         }
+    }
 
-        let sample_buf = SampleBuffer::<f32>::new(duration, spec);
-        let resampler = if spec.rate != config.sample_rate.0 {
-            Some(Resampler::new(
-                spec,
-                config.sample_rate.0 as usize,
-                duration,
-            ))
-        } else {
-            None
-        };
+    /// 输出流是否已失效，失效后需要重建
+    pub fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Acquire)
+    }
 
-        Ok(Self {
-            ring_buf_producer,
-            sample_buf,
-            stream,
-            resampler,
-            volume: 1.0,
-        })
+    /// 是否可以继续输出指定规格的音频，用于轨道之间复用同一个输出流
+    pub fn is_compatible(&self, spec: SignalSpec, duration: Duration) -> bool {
+        !self.is_failed()
+            && self.spec == spec
+            && duration <= self.duration
+            && self.device_generation == self.control.device_generation.load(Ordering::Acquire)
+    }
+
+    /// 输出延迟(秒)，即重采样器、时间伸缩器、环形缓冲区和暂停时留下的尚未播放的音频加上设备延迟，换算为媒体时间
+    pub fn latency(&self) -> f64 {
+        // 定位后等待丢弃的采样不会播放
+        let count = self.ring_buf.count() as u64;
+        let read = self.written.saturating_sub(count);
+        let discarding = self.discard_until.load(Ordering::Acquire).saturating_sub(read);
+        let buffered = count.saturating_sub(discarding) + self.pending.len() as u64;
+        let buffered_frames = buffered as usize / self.channels.max(1);
+        let device_latency = self.device_latency.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+        let output = (buffered_frames as f64 / self.sample_rate as f64 + device_latency) * self.playback_speed();
+
+        // 重采样器的输入是音源采样，时间伸缩器的输入是尚未伸缩的设备采样率音频
+        let resampler = self
+            .resampler
+            .as_ref()
+            .map_or(0.0, |resampler| resampler.delay_frames() / self.spec.rate as f64);
+        let stretcher = self
+            .stretcher
+            .as_ref()
+            .map_or(0.0, |stretcher| stretcher.pending_frames() / self.sample_rate as f64);
+        output + resampler + stretcher
     }
 
     /// 写入音频数据
@@ -170,64 +723,272 @@ impl AudioOutput {
             self.sample_buf.samples()
         };
 
-        // 应用音量控制并写入
-        if self.volume != 1.0 {
-            let mut adjusted_samples = Vec::with_capacity(samples.len());
-            for sample in samples {
-                let sample_f32: f32 = (*sample).into_sample();
-                let adjusted_sample = sample_f32 * self.volume;
-                adjusted_samples.push(adjusted_sample.into_sample());
-            }
-            let mut adjusted_slice = adjusted_samples.as_slice();
-            while let Some(written) = self.ring_buf_producer.write_blocking(adjusted_slice) {
-                adjusted_slice = &adjusted_slice[written..];
+        let samples = match &mut self.stretcher {
+            Some(stretcher) => stretcher.process(self.speed, samples),
+            None => samples,
+        };
+
+        let samples = match &self.matrix {
+            Some(matrix) => {
+                matrix.apply(samples, &mut self.mix_buf);
+                &self.mix_buf[..]
             }
+            None => samples,
+        };
+
+        // 音量在音频回调中逐采样渐变应用
+        self.written += Self::push_samples(
+            &self.ring_buf,
+            &self.ring_buf_producer,
+            &self.control,
+            &self.failed,
+            &mut self.pending,
+            samples,
+        );
+
+        Ok(())
+    }
+
+    /// 写入环形缓冲区，缓冲区满时等待音频回调读取；暂停时剩余的采样留到恢复后写入，输出流失效时直接返回
+    ///
+    /// 返回写入环形缓冲区的采样数
+    fn push_samples(
+        ring_buf: &SpscRb<f32>,
+        producer: &rb::Producer<f32>,
+        control: &OutputControl,
+        failed: &AtomicBool,
+        pending: &mut Vec<f32>,
+        samples: &[f32],
+    ) -> u64 {
+        let queued_len = pending.len() + samples.len();
+        if !pending.is_empty() {
+            pending.extend_from_slice(samples);
+            let queued = std::mem::take(pending);
+            let remaining = Self::write_ring(ring_buf, producer, control, failed, &queued);
+            pending.extend_from_slice(remaining);
         } else {
-            let mut samples_slice = samples;
-            while let Some(written) = self.ring_buf_producer.write_blocking(samples_slice) {
-                samples_slice = &samples_slice[written..];
+            let remaining = Self::write_ring(ring_buf, producer, control, failed, samples);
+            pending.extend_from_slice(remaining);
+        }
+        (queued_len - pending.len()) as u64
+    }
+
+    fn write_ring<'a>(
+        ring_buf: &SpscRb<f32>,
+        producer: &rb::Producer<f32>,
+        control: &OutputControl,
+        failed: &AtomicBool,
+        mut samples: &'a [f32],
+    ) -> &'a [f32] {
+        while !samples.is_empty() && !control.is_paused() && !failed.load(Ordering::Acquire) {
+            match producer.write(samples) {
+                Ok(written) => samples = &samples[written..],
+                Err(_) => control.wait_for_space(ring_buf, failed),
             }
         }
+        samples
+    }
 
-        Ok(())
+    /// 丢弃已写入但尚未播放的音频，定位后调用
+    pub fn discard_buffered(&mut self) {
+        self.pending.clear();
+        self.resampler = Self::create_resampler(
+            self.spec,
+            self.sample_rate,
+            self.duration,
+            self.speed,
+            self.speed_mode,
+        );
+        if let Some(stretcher) = self.stretcher.as_mut() {
+            stretcher.reset();
+        }
+        self.discard_until.store(self.written, Ordering::Release);
+    }
+
+    /// 标记新轨道的起点，音频回调播放到已写入的采样之后时通知处理链开始新轨道
+    pub fn mark_track_start(&mut self) {
+        // 重采样器中尚未输出的上一首音频也在起点之前
+        let resampler_samples = self.resampler.as_ref().map_or(0, |resampler| {
+            let input_rate = Self::resampler_input_rate(self.spec, self.speed, self.speed_mode) as f64;
+            let frames = resampler.buffered_frames() as f64 * self.sample_rate as f64 / input_rate;
+            frames.round() as u64 * self.channels as u64
+        });
+        let position = self.written + self.pending.len() as u64 + resampler_samples;
+        self.track_start.store(position, Ordering::Release);
     }
 
-    /// 刷新音频缓冲区
+    /// 是否有尚未播放到的轨道起点，丢弃输出流前检查以便在新的输出流上补发
+    pub fn has_pending_track_start(&self) -> bool {
+        self.track_start.load(Ordering::Acquire) != NO_TRACK_START
+    }
+
+    /// 刷新音频缓冲区，播放完剩余的音频后暂停输出流，停止时淡出后暂停
     pub fn flush(&mut self) {
-        if let Some(resampler) = &mut self.resampler {
-            let remaining_samples = resampler.flush().unwrap_or_default();
-            if self.volume != 1.0 {
-                let mut adjusted_samples = Vec::with_capacity(remaining_samples.len());
-                for sample in remaining_samples {
-                    let sample_f32: f32 = (*sample).into_sample();
-                    let adjusted_sample = sample_f32 * self.volume;
-                    adjusted_samples.push(adjusted_sample.into_sample());
-                }
-                let mut adjusted_slice = adjusted_samples.as_slice();
-                while let Some(written) = self.ring_buf_producer.write_blocking(adjusted_slice) {
-                    adjusted_slice = &adjusted_slice[written..];
-                }
+        let remaining = self
+            .resampler
+            .as_mut()
+            .and_then(Resampler::flush)
+            .map(<[f32]>::to_vec)
+            .unwrap_or_default();
+        // 重采样器剩余的输出同样需要伸缩，之后输出时间伸缩器中剩余的音频
+        match self.stretcher.take() {
+            Some(mut stretcher) => {
+                self.write_mixed(stretcher.process(self.speed, &remaining));
+                self.write_mixed(stretcher.flush());
+                self.stretcher = Some(stretcher);
+            }
+            None => self.write_mixed(&remaining),
+        }
+        if !self.is_failed() {
+            if self.control.is_paused() {
+                // 停止时先淡出，避免截断产生爆音
+                self.control.wait_until_silent();
             } else {
-                let mut remaining_slice = remaining_samples;
-                while let Some(written) = self.ring_buf_producer.write_blocking(remaining_slice) {
-                    remaining_slice = &remaining_slice[written..];
-                }
+                self.drain();
             }
         }
         let _ = self.stream.pause();
     }
 
-    /// 设置音量
-    pub fn set_volume(&mut self, volume: f32) -> Result<()> {
-        if volume < 0.0 || volume > 1.0 {
-            return Err(AudioOutputError::VolumeError);
+    /// 等待音频回调播放完环形缓冲区和设备缓冲区中的音频，切换输出流或播放结束时调用
+    fn drain(&self) {
+        let timeout = StdDuration::from_millis(STALL_TIMEOUT_MS);
+        let stalled = self.control.wait_while(timeout, || {
+            !self.ring_buf.is_empty() && !self.control.is_paused() && !self.is_failed()
+        });
+        if !stalled && !self.control.is_paused() && !self.is_failed() {
+            let device_latency = self.device_latency.load(Ordering::Relaxed);
+            std::thread::sleep(StdDuration::from_micros(device_latency));
         }
-        self.volume = volume;
+    }
+
+    /// 淡出后暂停输出设备
+    pub fn pause(&mut self) {
+        if self.is_failed() {
+            return;
+        }
+        self.control.wait_until_silent();
+        if let Err(e) = self.stream.pause() {
+            eprintln!("Stream pause failed: {:?}", e);
+        }
+    }
+
+    /// 恢复输出设备，并写入暂停时留下的采样；输出流已失效时留到下一次写入重建
+    pub fn resume(&mut self) -> Result<()> {
+        if self.is_failed() {
+            return Ok(());
+        }
+        self.stream.play().map_err(|_| AudioOutputError::PlayStreamError)?;
+        self.written += Self::push_samples(
+            &self.ring_buf,
+            &self.ring_buf_producer,
+            &self.control,
+            &self.failed,
+            &mut self.pending,
+            &[],
+        );
         Ok(())
     }
+
+    /// 设置播放速度和变速模式
+    pub fn set_speed(&mut self, speed: f32, speed_mode: SpeedMode) -> Result<()> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err(AudioOutputError::SpeedError);
+        }
+        if speed == self.speed && speed_mode == self.speed_mode {
+            return Ok(());
+        }
+
+        self.speed = speed;
+        self.speed_mode = speed_mode;
+
+        // 旧重采样器中尚未处理的输入交给新的重采样器，避免变速时丢失音频
+        let buffered = self.resampler.as_mut().map(Resampler::take_buffered).unwrap_or_default();
+        self.resampler =
+            Self::create_resampler(self.spec, self.sample_rate, self.duration, speed, speed_mode);
+
+        // 原速时绕过时间伸缩，避免不必要的处理；时间伸缩器每次处理时按当前速度伸缩，变速时保留状态
+        if speed_mode == SpeedMode::TimeStretch && speed != 1.0 {
+            if self.stretcher.is_none() {
+                self.stretcher = Some(TimeStretcher::new(self.spec.channels.count(), self.sample_rate));
+            }
+        } else if let Some(mut stretcher) = self.stretcher.take() {
+            // 不再伸缩时先输出时间伸缩器中剩余的音频，它们在重采样器缓冲的输入之前
+            self.write_mixed(stretcher.flush());
+        }
+
+        match self.resampler.as_mut() {
+            Some(resampler) => resampler.extend_buffered(&buffered),
+            None => self.write_buffered(&buffered),
+        }
+        Ok(())
+    }
+
+    /// 不再需要重采样时直接输出旧重采样器中尚未处理的输入
+    fn write_buffered(&mut self, buffered: &[Vec<f32>]) {
+        let frames = buffered.first().map_or(0, Vec::len);
+        if frames == 0 {
+            return;
+        }
+        let mut samples = Vec::with_capacity(frames * buffered.len());
+        for i in 0..frames {
+            samples.extend(buffered.iter().map(|channel| channel[i]));
+        }
+
+        match self.stretcher.take() {
+            Some(mut stretcher) => {
+                self.write_mixed(stretcher.process(self.speed, &samples));
+                self.stretcher = Some(stretcher);
+            }
+            None => self.write_mixed(&samples),
+        }
+    }
+
+    /// 转换声道后写入环形缓冲区
+    fn write_mixed(&mut self, samples: &[f32]) {
+        let samples = match &self.matrix {
+            Some(matrix) => {
+                matrix.apply(samples, &mut self.mix_buf);
+                &self.mix_buf[..]
+            }
+            None => samples,
+        };
+        self.written += Self::push_samples(
+            &self.ring_buf,
+            &self.ring_buf_producer,
+            &self.control,
+            &self.failed,
+            &mut self.pending,
+            samples,
+        );
+    }
 }
 
 /// 创建音频输出设备
-pub fn create_audio_output(spec: SignalSpec, duration: Duration) -> Result<AudioOutput> {
-    AudioOutput::new(spec, duration)
+pub fn create_audio_output(
+    spec: SignalSpec,
+    duration: Duration,
+    control: Arc<OutputControl>,
+) -> Result<AudioOutput> {
+    AudioOutput::new(spec, duration, control)
+}
+
+/// 输出流失效后重建音频输出，失败时等待系统切换默认设备后重试
+pub fn recover_audio_output(
+    spec: SignalSpec,
+    duration: Duration,
+    control: Arc<OutputControl>,
+) -> Result<AudioOutput> {
+    let mut attempt = 1;
+    loop {
+        match AudioOutput::new(spec, duration, Arc::clone(&control)) {
+            Ok(output) => return Ok(output),
+            Err(e) if attempt >= RECOVERY_ATTEMPTS => return Err(e),
+            Err(e) => {
+                eprintln!("Output recovery attempt {} failed: {}", attempt, e);
+                std::thread::sleep(StdDuration::from_millis(RECOVERY_INTERVAL_MS));
+                attempt += 1;
+            }
+        }
+    }
 }
@@ -0,0 +1,123 @@
+//! 回放增益模块
+//!
+//! 读取 ReplayGain 和 R128 标签，按轨道或专辑增益统一不同来源音频的响度
+
+use crate::audio::gain::{GainRamp, gain_to_db};
+use symphonia::core::audio::{AudioBuffer, Signal};
+use symphonia::core::meta::Tag;
+
+/// R128 标签以 -23 LUFS 为参考，ReplayGain 以 -18 LUFS 为参考
+const R128_REFERENCE_OFFSET_DB: f32 = 5.0;
+
+/// 回放增益模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayGainMode {
+    /// 关闭
+    Off,
+    /// 轨道增益，缺失时使用专辑增益
+    Track,
+    /// 专辑增益，缺失时使用轨道增益
+    Album,
+}
+
+impl ReplayGainMode {
+    /// 根据模式编号获取回放增益模式
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(ReplayGainMode::Off),
+            1 => Some(ReplayGainMode::Track),
+            2 => Some(ReplayGainMode::Album),
+            _ => None,
+        }
+    }
+}
+
+/// 元数据中的回放增益(dB)和峰值(线性)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ReplayGainTags {
+    pub track_gain: Option<f32>,
+    pub track_peak: Option<f32>,
+    pub album_gain: Option<f32>,
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGainTags {
+    /// 读取 REPLAYGAIN_* 标签，没有时读取 R128_*_GAIN 标签
+    pub fn from_tags(tags: &[Tag]) -> Option<Self> {
+        let find = |name: &str| {
+            tags.iter()
+                .find(|tag| tag.key.to_ascii_uppercase().ends_with(name))
+                .map(|tag| tag.value.to_string())
+        };
+        let parse_gain = |name: &str| {
+            // 增益值带有 " dB" 后缀
+            find(name).and_then(|value| {
+                value
+                    .trim()
+                    .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace())
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|gain| gain.is_finite())
+            })
+        };
+        let parse_peak = |name: &str| {
+            find(name)
+                .and_then(|value| value.trim().parse::<f32>().ok())
+                .filter(|peak| peak.is_finite() && *peak > 0.0)
+        };
+        // R128 增益是 Q7.8 定点数
+        let parse_r128 = |name: &str| {
+            find(name)
+                .and_then(|value| value.trim().parse::<i16>().ok())
+                .map(|gain| gain as f32 / 256.0 + R128_REFERENCE_OFFSET_DB)
+        };
+
+        let tags = Self {
+            track_gain: parse_gain("REPLAYGAIN_TRACK_GAIN").or_else(|| parse_r128("R128_TRACK_GAIN")),
+            track_peak: parse_peak("REPLAYGAIN_TRACK_PEAK"),
+            album_gain: parse_gain("REPLAYGAIN_ALBUM_GAIN").or_else(|| parse_r128("R128_ALBUM_GAIN")),
+            album_peak: parse_peak("REPLAYGAIN_ALBUM_PEAK"),
+        };
+        (!tags.is_empty()).then_some(tags)
+    }
+
+    /// 是否没有任何增益标签
+    pub fn is_empty(&self) -> bool {
+        self.track_gain.is_none() && self.album_gain.is_none()
+    }
+
+    /// 按模式计算应用的增益(dB)，增益后的峰值超过满幅时降低增益防止削波
+    pub fn gain_db(&self, mode: ReplayGainMode) -> f32 {
+        let track = self.track_gain.map(|gain| (gain, self.track_peak));
+        let album = self.album_gain.map(|gain| (gain, self.album_peak));
+        let selected = match mode {
+            ReplayGainMode::Off => None,
+            ReplayGainMode::Track => track.or(album),
+            ReplayGainMode::Album => album.or(track),
+        };
+
+        match selected {
+            Some((gain, Some(peak))) => gain.min(-gain_to_db(peak)),
+            Some((gain, None)) => gain,
+            None => 0.0,
+        }
+    }
+}
+
+/// 对平面格式的音频逐帧应用增益渐变
+pub fn apply_gain(buf: &mut AudioBuffer<f32>, ramp: &mut GainRamp) {
+    if !ramp.is_ramping() {
+        let gain = ramp.target();
+        if gain != 1.0 {
+            buf.transform(|sample| sample * gain);
+        }
+        return;
+    }
+
+    let gains: Vec<f32> = (0..buf.frames()).map(|_| ramp.advance()).collect();
+    for c in 0..buf.spec().channels.count() {
+        for (sample, gain) in buf.chan_mut(c).iter_mut().zip(gains.iter()) {
+            *sample *= gain;
+        }
+    }
+}
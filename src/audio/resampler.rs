@@ -9,6 +9,13 @@ pub struct Resampler<T> {
     output: Vec<Vec<f32>>,
     interleaved: Vec<T>,
     duration: usize,
+    input_rate: usize,
+    output_rate: usize,
+    /// FFT 重采样滤波器的延迟(输入帧)
+    filter_delay: usize,
+    /// 已交给重采样器的输入帧数和得到的输出帧数
+    consumed: u64,
+    produced: u64,
 }
 
 impl<T> Resampler<T>
@@ -34,6 +41,8 @@ where
         for channel in self.input.iter_mut() {
             channel.drain(0..self.duration);
         }
+        self.consumed += self.duration as u64;
+        self.produced += self.output[0].len() as u64;
 
         let num_channels = self.output.len();
         self.interleaved
@@ -69,12 +78,21 @@ where
         let output = rubato::Resampler::output_buffer_allocate(&resampler);
         let input = vec![Vec::with_capacity(duration); num_channels];
 
+        // 与 FftFixedIn 按采样率最大公约数和子块数选择 FFT 长度的方式一致，滤波器中心位于 FFT 长度的一半
+        let min_chunk_in = spec.rate as usize / gcd(spec.rate as usize, to_sample_rate);
+        let fft_size_in = (duration / 2).div_ceil(min_chunk_in) * min_chunk_in;
+
         Self {
             resampler,
             input,
             output,
             duration,
             interleaved: Default::default(),
+            input_rate: spec.rate as usize,
+            output_rate: to_sample_rate,
+            filter_delay: fft_size_in / 2,
+            consumed: 0,
+            produced: 0,
         }
     }
 
@@ -88,6 +106,26 @@ where
         Some(self.resample_inner())
     }
 
+    pub fn buffered_frames(&self) -> usize {
+        self.input[0].len()
+    }
+
+    // 尚未输出的输入帧数，包括等待凑满一块的输入、重采样器内部保存的输入和滤波器延迟
+    pub fn delay_frames(&self) -> f64 {
+        let saved = self.consumed as f64 - self.produced as f64 * self.input_rate as f64 / self.output_rate as f64;
+        self.buffered_frames() as f64 + saved.max(0.0) + self.filter_delay as f64
+    }
+
+    pub fn take_buffered(&mut self) -> Vec<Vec<f32>> {
+        self.input.iter_mut().map(std::mem::take).collect()
+    }
+
+    pub fn extend_buffered(&mut self, input: &[Vec<f32>]) {
+        for (dst, src) in self.input.iter_mut().zip(input) {
+            dst.extend_from_slice(src);
+        }
+    }
+
     pub fn flush(&mut self) -> Option<&[T]> {
         let len = self.input[0].len();
 
@@ -107,6 +145,10 @@ where
     }
 }
 
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
 fn convert_samples_any(input: &AudioBufferRef<'_>, output: &mut [Vec<f32>]) {
     match input {
         AudioBufferRef::U8(input) => convert_samples(input, output),
@@ -131,3 +173,62 @@ where
         dst.extend(src.iter().map(|&s| s.into_sample()));
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::audio::Channels;
+
+    const DURATION: u64 = 1152;
+
+    /// 将单声道采样按块送入重采样器，返回全部输出
+    fn resample(resampler: &mut Resampler<f32>, spec: SignalSpec, samples: &[f32]) -> Vec<f32> {
+        let mut output = Vec::new();
+        for chunk in samples.chunks(DURATION as usize) {
+            let mut buffer = AudioBuffer::<f32>::new(DURATION, spec);
+            buffer.render_reserved(Some(chunk.len()));
+            buffer.chan_mut(0).copy_from_slice(chunk);
+            if let Some(resampled) = resampler.resample(AudioBufferRef::F32(std::borrow::Cow::Borrowed(&buffer))) {
+                output.extend_from_slice(resampled);
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn filter_delay_matches_impulse_response() {
+        let spec = SignalSpec::new(44500, Channels::FRONT_LEFT);
+        let mut resampler = Resampler::<f32>::new(spec, 48000, DURATION);
+        // 取整后的采样率公约数较大，滤波器延迟只有几毫秒
+        assert!(resampler.filter_delay < 1000, "delay {}", resampler.filter_delay);
+
+        let mut input = vec![0.0; 44500];
+        input[10000] = 1.0;
+        let output = resample(&mut resampler, spec, &input);
+        let peak = output
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(index, _)| index)
+            .unwrap();
+
+        let delay = peak as f64 * 44500.0 / 48000.0 - 10000.0;
+        assert!((delay - resampler.filter_delay as f64).abs() < 2.0, "delay {delay}");
+    }
+
+    #[test]
+    fn delay_counts_unprocessed_input() {
+        let spec = SignalSpec::new(44100, Channels::FRONT_LEFT);
+        let mut resampler = Resampler::<f32>::new(spec, 48000, DURATION);
+        let base = resampler.delay_frames();
+        assert_eq!(base, resampler.filter_delay as f64);
+
+        resample(&mut resampler, spec, &[0.0; 500]);
+        assert_eq!(resampler.delay_frames(), base + 500.0);
+
+        // 处理的输入与得到的输出之差即重采样器内部保存的输入
+        resample(&mut resampler, spec, &vec![0.0; DURATION as usize * 10]);
+        let delay = resampler.delay_frames() - base;
+        assert!((0.0..DURATION as f64 * 2.0).contains(&delay), "delay {delay}");
+    }
+}
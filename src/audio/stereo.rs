@@ -0,0 +1,253 @@
+//! 立体声模块
+//!
+//! 提供左右平衡、声像、声道增益、单声道混音和左右声道互换，以 2x2 矩阵作用于前两个声道
+//!
+//! 多声道输出时只处理前左和前右声道，中置、低频和环绕声道保持不变
+
+use crate::audio::dsp::AudioProcessor;
+use crate::audio::gain::{MIN_VOLUME_DB, db_to_gain};
+use std::f32::consts::{FRAC_PI_4, SQRT_2};
+
+/// 矩阵变化的渐变时长(毫秒)
+const STEREO_RAMP_MS: u64 = 20;
+
+/// 不改变声音的矩阵
+const IDENTITY: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+/// 立体声设置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StereoSettings {
+    /// 左右平衡(-1.0 到 1.0)，负值衰减右声道，正值衰减左声道
+    pub balance: f32,
+    /// 声像(-1.0 到 1.0)，按等功率声像律将声音移向一侧，居中时不改变音量，完全偏向一侧时该侧提升 3dB
+    pub pan: f32,
+    /// 左声道增益(dB)
+    pub left_gain_db: f32,
+    /// 右声道增益(dB)
+    pub right_gain_db: f32,
+    /// 混合为单声道，多声道输出时只混合前左和前右声道
+    pub mono: bool,
+    /// 互换左右声道
+    pub swap: bool,
+}
+
+impl StereoSettings {
+    /// 设置参数，返回参数是否有效
+    pub fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        match name {
+            "balance" if (-1.0..=1.0).contains(&value) => self.balance = value,
+            "pan" if (-1.0..=1.0).contains(&value) => self.pan = value,
+            "left_gain" if (MIN_VOLUME_DB..=0.0).contains(&value) => self.left_gain_db = value,
+            "right_gain" if (MIN_VOLUME_DB..=0.0).contains(&value) => self.right_gain_db = value,
+            "mono" => self.mono = value != 0.0,
+            "swap" => self.swap = value != 0.0,
+            _ => return false,
+        }
+        true
+    }
+
+    /// 全部参数名称和值
+    pub fn parameters(&self) -> [(&'static str, f32); 6] {
+        [
+            ("balance", self.balance),
+            ("pan", self.pan),
+            ("left_gain", self.left_gain_db),
+            ("right_gain", self.right_gain_db),
+            ("mono", self.mono as i32 as f32),
+            ("swap", self.swap as i32 as f32),
+        ]
+    }
+
+    /// 输出左右声道的混合矩阵 [左<-左, 左<-右, 右<-左, 右<-右]
+    fn matrix(&self) -> [f32; 4] {
+        let mut matrix = match (self.mono, self.swap) {
+            (true, _) => [0.5; 4],
+            (false, true) => [0.0, 1.0, 1.0, 0.0],
+            (false, false) => IDENTITY,
+        };
+
+        // 等功率声像律，左右增益的平方和保持为 2，居中时直接取 1，使默认设置仍是恒等矩阵
+        let (pan_left, pan_right) = if self.pan == 0.0 {
+            (1.0, 1.0)
+        } else {
+            let (sin, cos) = ((self.pan + 1.0) * FRAC_PI_4).sin_cos();
+            (cos * SQRT_2, sin * SQRT_2)
+        };
+
+        let left = db_to_gain(self.left_gain_db) * (1.0 - self.balance).min(1.0) * pan_left;
+        let right = db_to_gain(self.right_gain_db) * (1.0 + self.balance).min(1.0) * pan_right;
+        matrix[0] *= left;
+        matrix[1] *= left;
+        matrix[2] *= right;
+        matrix[3] *= right;
+        matrix
+    }
+}
+
+impl Default for StereoSettings {
+    fn default() -> Self {
+        Self {
+            balance: 0.0,
+            pan: 0.0,
+            left_gain_db: 0.0,
+            right_gain_db: 0.0,
+            mono: false,
+            swap: false,
+        }
+    }
+}
+
+/// 立体声处理器，设置变化时矩阵逐帧渐变，避免切换单声道或互换声道时产生爆音
+pub struct StereoMixer {
+    settings: StereoSettings,
+    current: [f32; 4],
+    target: [f32; 4],
+    step: [f32; 4],
+    remaining: usize,
+    sample_rate: u32,
+}
+
+impl StereoMixer {
+    /// 创建立体声处理器
+    pub fn new() -> Self {
+        Self {
+            settings: StereoSettings::default(),
+            current: IDENTITY,
+            target: IDENTITY,
+            step: [0.0; 4],
+            remaining: 0,
+            sample_rate: 0,
+        }
+    }
+
+    fn advance(&mut self) -> [f32; 4] {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+            if self.remaining == 0 {
+                self.current = self.target;
+            } else {
+                for (current, step) in self.current.iter_mut().zip(self.step) {
+                    *current += step;
+                }
+            }
+        }
+        self.current
+    }
+}
+
+impl Default for StereoMixer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioProcessor for StereoMixer {
+    fn name(&self) -> &str {
+        "stereo"
+    }
+
+    fn configure(&mut self, sample_rate: u32, _channels: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    fn process(&mut self, samples: &mut [f32], channels: usize) {
+        if channels < 2 || (self.remaining == 0 && self.current == IDENTITY) {
+            return;
+        }
+
+        for frame in samples.chunks_exact_mut(channels) {
+            let [ll, lr, rl, rr] = self.advance();
+            let (left, right) = (frame[0], frame[1]);
+            frame[0] = ll * left + lr * right;
+            frame[1] = rl * left + rr * right;
+        }
+    }
+
+    fn reset(&mut self) {
+        self.current = self.target;
+        self.remaining = 0;
+    }
+
+    fn set_parameter(&mut self, name: &str, value: f32) -> bool {
+        if !self.settings.set_parameter(name, value) {
+            return false;
+        }
+
+        self.target = self.settings.matrix();
+        let frames = (STEREO_RAMP_MS * self.sample_rate as u64 / 1000) as usize;
+        if frames == 0 {
+            self.current = self.target;
+            self.remaining = 0;
+        } else {
+            for ((step, target), current) in self.step.iter_mut().zip(self.target).zip(self.current) {
+                *step = (target - current) / frames as f32;
+            }
+            self.remaining = frames;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(name: &str, value: f32) -> StereoSettings {
+        let mut settings = StereoSettings::default();
+        assert!(settings.set_parameter(name, value));
+        settings
+    }
+
+    fn assert_matrix(actual: [f32; 4], expected: [f32; 4]) {
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn default_is_identity() {
+        assert_matrix(StereoSettings::default().matrix(), IDENTITY);
+        assert_matrix(settings("pan", 0.0).matrix(), IDENTITY);
+    }
+
+    #[test]
+    fn pan_keeps_constant_power() {
+        for pan in [-1.0, -0.5, 0.25, 0.8, 1.0] {
+            let [left, _, _, right] = settings("pan", pan).matrix();
+            assert!((left * left + right * right - 2.0).abs() < 1e-5, "pan {pan}");
+        }
+    }
+
+    #[test]
+    fn hard_pan_moves_mono_content_to_one_side() {
+        let mut settings = settings("pan", 1.0);
+        settings.set_parameter("mono", 1.0);
+        let half = SQRT_2 / 2.0;
+        assert_matrix(settings.matrix(), [0.0, 0.0, half, half]);
+    }
+
+    #[test]
+    fn balance_only_attenuates() {
+        assert_matrix(settings("balance", 0.5).matrix(), [0.5, 0.0, 0.0, 1.0]);
+        assert_matrix(settings("balance", -1.0).matrix(), [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn out_of_range_parameters_are_rejected() {
+        let mut settings = StereoSettings::default();
+        assert!(!settings.set_parameter("pan", 1.5));
+        assert!(!settings.set_parameter("balance", -2.0));
+        assert!(!settings.set_parameter("left_gain", 3.0));
+        assert!(!settings.set_parameter("width", 1.0));
+    }
+
+    #[test]
+    fn mixer_only_touches_front_channels() {
+        let mut mixer = StereoMixer::new();
+        mixer.configure(0, 6);
+        assert!(mixer.set_parameter("swap", 1.0));
+        let mut frame = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        mixer.process(&mut frame, 6);
+        assert_eq!(frame, [2.0, 1.0, 3.0, 4.0, 5.0, 6.0]);
+    }
+}
@@ -0,0 +1,267 @@
+//! 变速模块
+//!
+//! 提供基于 WSOLA 的保持音调变速处理
+
+use std::f32::consts::PI;
+
+/// 最小播放速度
+pub const MIN_SPEED: f32 = 0.5;
+/// 最大播放速度
+pub const MAX_SPEED: f32 = 2.0;
+
+/// 变速模式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpeedMode {
+    /// 重采样变速，音调随速度变化
+    Resample,
+    /// 时间伸缩变速，保持音调
+    TimeStretch,
+}
+
+impl SpeedMode {
+    /// 根据模式编号获取变速模式
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(SpeedMode::Resample),
+            1 => Some(SpeedMode::TimeStretch),
+            _ => None,
+        }
+    }
+}
+
+/// 相关性计算时的采样步长，降低搜索开销
+const CORRELATION_STRIDE: usize = 4;
+
+/// WSOLA 时间伸缩器，处理交错格式的 f32 音频
+pub struct TimeStretcher {
+    channels: usize,
+    /// 分析帧长度(帧)
+    frame_len: usize,
+    /// 合成步长(帧)，为帧长度的一半
+    hop: usize,
+    /// 最佳拼接位置的搜索范围(帧)
+    search: usize,
+    window: Vec<f32>,
+    /// 待处理的输入
+    input: Vec<f32>,
+    /// 下一帧在输入中的理论位置
+    analysis_pos: f64,
+    /// 上一帧在输入中的自然延续位置
+    natural_pos: Option<usize>,
+    /// 重叠相加缓冲区
+    overlap: Vec<f32>,
+    output: Vec<f32>,
+}
+
+impl TimeStretcher {
+    /// 创建时间伸缩器
+    pub fn new(channels: usize, sample_rate: u32) -> Self {
+        // 30ms 分析帧，10ms 搜索范围
+        let frame_len = ((sample_rate as usize * 30) / 1000).max(64) & !1;
+        let hop = frame_len / 2;
+        let search = (sample_rate as usize * 10) / 1000;
+
+        // 周期汉宁窗在 50% 重叠时相加恒为 1
+        let window = (0..frame_len)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / frame_len as f32).cos())
+            .collect();
+
+        Self {
+            channels: channels.max(1),
+            frame_len,
+            hop,
+            search,
+            window,
+            input: Vec::new(),
+            analysis_pos: 0.0,
+            natural_pos: None,
+            overlap: vec![0.0; frame_len * channels.max(1)],
+            output: Vec::new(),
+        }
+    }
+
+    /// 清空内部缓冲
+    pub fn reset(&mut self) {
+        self.input.clear();
+        self.analysis_pos = 0.0;
+        self.natural_pos = None;
+        self.overlap.iter_mut().for_each(|s| *s = 0.0);
+        self.output.clear();
+    }
+
+    /// 尚未伸缩输出的输入帧数
+    pub fn pending_frames(&self) -> f64 {
+        (self.input.len() / self.channels) as f64 - self.analysis_pos
+    }
+
+    /// 不再伸缩时输出剩余的音频并清空内部缓冲
+    ///
+    /// 重叠缓冲区与下一帧的前半窗相加恰好还原为自然延续位置之后的原始输入，因此直接输出这部分输入即可与已输出的音频连续
+    pub fn flush(&mut self) -> &[f32] {
+        let start = self.natural_pos.unwrap_or(self.analysis_pos.round() as usize) * self.channels;
+        let remaining = self.input.split_off(start.min(self.input.len()));
+        self.reset();
+        self.output = remaining;
+        &self.output
+    }
+
+    /// 处理一段交错音频，返回按速度伸缩后的输出
+    pub fn process(&mut self, speed: f32, samples: &[f32]) -> &[f32] {
+        let channels = self.channels;
+        self.input.extend_from_slice(samples);
+        self.output.clear();
+
+        loop {
+            let available = self.input.len() / channels;
+            let nominal = self.analysis_pos.round() as usize;
+
+            if nominal + self.search + self.frame_len > available {
+                break;
+            }
+            if self.natural_pos.is_some_and(|natural| natural + self.frame_len > available) {
+                break;
+            }
+
+            let pos = match self.natural_pos {
+                Some(natural) => self.best_position(nominal, natural),
+                None => nominal,
+            };
+
+            // 重叠相加
+            for i in 0..self.frame_len {
+                let gain = self.window[i];
+                for ch in 0..channels {
+                    self.overlap[i * channels + ch] += gain * self.input[(pos + i) * channels + ch];
+                }
+            }
+
+            self.output.extend_from_slice(&self.overlap[..self.hop * channels]);
+            self.overlap.drain(..self.hop * channels);
+            self.overlap.resize(self.frame_len * channels, 0.0);
+
+            self.natural_pos = Some(pos + self.hop);
+            self.analysis_pos += self.hop as f64 * speed as f64;
+
+            // 丢弃之后不会再用到的输入
+            let next_min = (self.analysis_pos.floor() as usize).saturating_sub(self.search);
+            let consumed = next_min.min(pos + self.hop);
+            if consumed > 0 {
+                self.input.drain(..consumed * channels);
+                self.analysis_pos -= consumed as f64;
+                self.natural_pos = self.natural_pos.map(|natural| natural - consumed);
+            }
+        }
+
+        &self.output
+    }
+
+    /// 在理论位置附近搜索与上一帧延续部分最相似的位置
+    fn best_position(&self, nominal: usize, natural: usize) -> usize {
+        let start = nominal.saturating_sub(self.search);
+        let end = nominal + self.search;
+
+        let mut best = nominal;
+        let mut best_score = f32::MIN;
+
+        for candidate in start..=end {
+            let mut correlation = 0.0;
+            let mut energy = 0.0;
+            for i in (0..self.frame_len).step_by(CORRELATION_STRIDE) {
+                let a = self.mono(candidate + i);
+                let b = self.mono(natural + i);
+                correlation += a * b;
+                energy += a * a;
+            }
+
+            let score = correlation / energy.sqrt().max(f32::EPSILON);
+            if score > best_score {
+                best_score = score;
+                best = candidate;
+            }
+        }
+
+        best
+    }
+
+    fn mono(&self, frame: usize) -> f32 {
+        let offset = frame * self.channels;
+        self.input[offset..offset + self.channels].iter().sum::<f32>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: u32 = 16000;
+
+    /// 分块处理 1 秒的立体声正弦波，返回输出帧数
+    fn stretched_frames(speed: f32) -> usize {
+        let mut stretcher = TimeStretcher::new(2, RATE);
+        let input: Vec<f32> = (0..RATE as usize)
+            .flat_map(|i| {
+                let sample = (2.0 * PI * 440.0 * i as f32 / RATE as f32).sin() * 0.5;
+                [sample, sample]
+            })
+            .collect();
+
+        input
+            .chunks(1024 * 2)
+            .map(|chunk| stretcher.process(speed, chunk).len() / 2)
+            .sum()
+    }
+
+    /// 输出长度与理论值的差距应在一个分析帧和搜索范围之内
+    fn assert_length(speed: f32) {
+        let expected = RATE as f32 / speed;
+        let tolerance = (RATE * 40 / 1000) as f32 / speed;
+        let frames = stretched_frames(speed) as f32;
+        assert!(
+            (frames - expected).abs() <= tolerance,
+            "speed {speed}: {frames} frames, expected about {expected}"
+        );
+    }
+
+    #[test]
+    fn output_length_follows_speed() {
+        assert_length(0.5);
+        assert_length(1.0);
+        assert_length(1.5);
+        assert_length(2.0);
+    }
+
+    #[test]
+    fn reset_clears_buffered_input() {
+        let mut stretcher = TimeStretcher::new(1, RATE);
+        stretcher.process(1.0, &[0.5; 512]);
+        stretcher.reset();
+        assert!(stretcher.process(1.0, &[0.5; 64]).is_empty());
+    }
+
+    #[test]
+    fn flush_continues_with_remaining_input() {
+        let mut stretcher = TimeStretcher::new(1, RATE);
+        let input: Vec<f32> = (0..RATE as usize).map(|i| (i as f32 * 0.01).sin()).collect();
+
+        let mut output = stretcher.process(1.0, &input).to_vec();
+        assert!(output.len() < input.len());
+        assert!(stretcher.pending_frames() > 0.0);
+        output.extend_from_slice(stretcher.flush());
+
+        // 原速时除开头的淡入外输出应与输入一致，末尾不丢失音频
+        assert_eq!(output.len(), input.len());
+        let hop = stretcher.hop;
+        for (i, (output, input)) in output.iter().zip(&input).enumerate().skip(hop) {
+            assert!((output - input).abs() < 1e-4, "frame {i}: {output} != {input}");
+        }
+        assert_eq!(stretcher.pending_frames(), 0.0);
+        assert!(stretcher.process(1.0, &[0.5; 64]).is_empty());
+    }
+
+    #[test]
+    fn speed_mode_from_code() {
+        assert_eq!(SpeedMode::from_code(0), Some(SpeedMode::Resample));
+        assert_eq!(SpeedMode::from_code(1), Some(SpeedMode::TimeStretch));
+        assert_eq!(SpeedMode::from_code(2), None);
+    }
+}
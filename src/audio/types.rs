@@ -7,6 +7,12 @@ pub enum AudioOutputError {
     PlayStreamError,
     /// 音量设置失败
     VolumeError,
+    /// 播放速度设置失败
+    SpeedError,
+    /// 音频设备不存在
+    DeviceNotFound,
+    /// 音频后端不可用
+    HostUnavailable,
 }
 
 impl std::fmt::Display for AudioOutputError {
@@ -15,6 +21,9 @@ impl std::fmt::Display for AudioOutputError {
             AudioOutputError::OpenStreamError => write!(f, "打开音频流失败"),
             AudioOutputError::PlayStreamError => write!(f, "播放音频流失败"),
             AudioOutputError::VolumeError => write!(f, "音量设置失败"),
+            AudioOutputError::SpeedError => write!(f, "播放速度设置失败"),
+            AudioOutputError::DeviceNotFound => write!(f, "音频设备不存在"),
+            AudioOutputError::HostUnavailable => write!(f, "音频后端不可用"),
         }
     }
 }
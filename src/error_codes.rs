@@ -1,7 +1,9 @@
+use crate::audio::types::AudioOutputError;
+
 /// ZMusic Player 错误码定义
 /// 与 Java ErrorCode 枚举保持一致
 #[repr(i32)]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ErrorCode {
     // 成功
     Success = 0,
@@ -26,6 +28,7 @@ pub enum ErrorCode {
     PlayerLockFailed = 3002,
     PlayerOperationFailed = 3003,
     PlayerThreadError = 3004,
+    QueueIndexOutOfBounds = 3005,
     
     // 网络相关错误 (4000-4999)
     NetworkError = 4000,
@@ -41,6 +44,11 @@ pub enum ErrorCode {
     AudioDecodeError = 5002,
     AudioOutputError = 5003,
     AudioVolumeError = 5004,
+    AudioSpeedError = 5005,
+    AudioProcessorNotFound = 5006,
+    EqualizerPresetNotFound = 5007,
+    AudioDeviceNotFound = 5008,
+    AudioHostUnavailable = 5009,
     
     // 媒体相关错误 (6000-6999)
     MediaNotFound = 6000,
@@ -75,6 +83,7 @@ impl ErrorCode {
             ErrorCode::PlayerLockFailed => "player lock failed",
             ErrorCode::PlayerOperationFailed => "player operation failed",
             ErrorCode::PlayerThreadError => "player thread error",
+            ErrorCode::QueueIndexOutOfBounds => "queue index out of bounds",
             ErrorCode::NetworkError => "network error",
             ErrorCode::UrlInvalid => "url invalid",
             ErrorCode::UrlUnsupportedProtocol => "unsupported protocol",
@@ -86,6 +95,11 @@ impl ErrorCode {
             ErrorCode::AudioDecodeError => "audio decode error",
             ErrorCode::AudioOutputError => "audio output error",
             ErrorCode::AudioVolumeError => "audio volume error",
+            ErrorCode::AudioSpeedError => "audio speed error",
+            ErrorCode::AudioProcessorNotFound => "audio processor not found",
+            ErrorCode::EqualizerPresetNotFound => "equalizer preset not found",
+            ErrorCode::AudioDeviceNotFound => "audio device not found",
+            ErrorCode::AudioHostUnavailable => "audio host unavailable",
             ErrorCode::MediaNotFound => "media not found",
             ErrorCode::MediaFormatUnsupported => "unsupported media format",
             ErrorCode::MediaCorrupted => "media corrupted",
@@ -123,6 +137,7 @@ impl ErrorCode {
             3002 => ErrorCode::PlayerLockFailed,
             3003 => ErrorCode::PlayerOperationFailed,
             3004 => ErrorCode::PlayerThreadError,
+            3005 => ErrorCode::QueueIndexOutOfBounds,
             4000 => ErrorCode::NetworkError,
             4001 => ErrorCode::UrlInvalid,
             4002 => ErrorCode::UrlUnsupportedProtocol,
@@ -134,6 +149,11 @@ impl ErrorCode {
             5002 => ErrorCode::AudioDecodeError,
             5003 => ErrorCode::AudioOutputError,
             5004 => ErrorCode::AudioVolumeError,
+            5005 => ErrorCode::AudioSpeedError,
+            5006 => ErrorCode::AudioProcessorNotFound,
+            5007 => ErrorCode::EqualizerPresetNotFound,
+            5008 => ErrorCode::AudioDeviceNotFound,
+            5009 => ErrorCode::AudioHostUnavailable,
             6000 => ErrorCode::MediaNotFound,
             6001 => ErrorCode::MediaFormatUnsupported,
             6002 => ErrorCode::MediaCorrupted,
@@ -161,3 +181,97 @@ impl From<ErrorCode> for i32 {
         error_code.code()
     }
 }
+
+/// 带错误码和详细信息的播放器错误
+#[derive(Debug, Clone)]
+pub struct PlayerError {
+    /// 错误码
+    pub code: ErrorCode,
+    /// 详细信息
+    pub message: String,
+}
+
+impl PlayerError {
+    /// 创建播放器错误
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// 创建格式化的错误消息
+    pub fn format_message(&self) -> String {
+        format!("{}: {}", self.code.format_message(), self.message)
+    }
+}
+
+impl std::fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format_message())
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+impl From<ureq::Error> for PlayerError {
+    fn from(error: ureq::Error) -> Self {
+        let code = match &error {
+            ureq::Error::Status(404, _) => ErrorCode::MediaNotFound,
+            ureq::Error::Status(_, _) => ErrorCode::HttpError,
+            ureq::Error::Transport(transport) => match transport.kind() {
+                ureq::ErrorKind::InvalidUrl => ErrorCode::UrlInvalid,
+                ureq::ErrorKind::UnknownScheme => ErrorCode::UrlUnsupportedProtocol,
+                ureq::ErrorKind::ConnectionFailed => ErrorCode::ConnectionRefused,
+                _ => ErrorCode::NetworkError,
+            },
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<std::io::Error> for PlayerError {
+    fn from(error: std::io::Error) -> Self {
+        // 网络媒体源在读取错误中携带了原始的播放器错误
+        if let Some(inner) = error.get_ref().and_then(|inner| inner.downcast_ref::<PlayerError>()) {
+            return inner.clone();
+        }
+
+        let code = match error.kind() {
+            std::io::ErrorKind::TimedOut => ErrorCode::ConnectionTimeout,
+            std::io::ErrorKind::ConnectionRefused => ErrorCode::ConnectionRefused,
+            _ => ErrorCode::MediaReadError,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<symphonia::core::errors::Error> for PlayerError {
+    fn from(error: symphonia::core::errors::Error) -> Self {
+        use symphonia::core::errors::Error;
+
+        let code = match error {
+            Error::IoError(error) => return Self::from(error),
+            Error::DecodeError(_) => ErrorCode::AudioDecodeError,
+            Error::SeekError(_) => ErrorCode::MediaReadError,
+            Error::Unsupported(_) => ErrorCode::MediaFormatUnsupported,
+            Error::LimitError(_) => ErrorCode::MediaCorrupted,
+            Error::ResetRequired => ErrorCode::AudioDecodeError,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+impl From<AudioOutputError> for PlayerError {
+    fn from(error: AudioOutputError) -> Self {
+        let code = match error {
+            AudioOutputError::OpenStreamError => ErrorCode::AudioDeviceError,
+            AudioOutputError::PlayStreamError => ErrorCode::AudioOutputError,
+            AudioOutputError::VolumeError => ErrorCode::AudioVolumeError,
+            AudioOutputError::SpeedError => ErrorCode::AudioSpeedError,
+            AudioOutputError::DeviceNotFound => ErrorCode::AudioDeviceNotFound,
+            AudioOutputError::HostUnavailable => ErrorCode::AudioHostUnavailable,
+        };
+        Self::new(code, error.to_string())
+    }
+}
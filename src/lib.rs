@@ -2,29 +2,121 @@ pub mod audio;
 pub mod error_codes;
 pub mod player;
 
-use crate::error_codes::ErrorCode;
-use crate::player::StreamPlayer;
+use crate::audio::crossfade::CrossfadeCurve;
+use crate::audio::device::{devices_to_json, hosts_to_json, list_hosts, list_output_devices};
+use crate::audio::equalizer::{EqBand, EqMode, FilterType};
+use crate::audio::replaygain::ReplayGainMode;
+use crate::audio::gain::{MAX_PREAMP_DB, VolumeCurve};
+use crate::audio::stretch::{MAX_SPEED, MIN_SPEED, SpeedMode};
+use crate::error_codes::{ErrorCode, PlayerError};
+use crate::player::{LoopRegion, PlayOptions, PlayerEvent, PlayerListener, PreparedTrack, StreamPlayer};
 use ez_jni::utils::get_env;
 use ez_jni::*;
+use jni::objects::{GlobalRef, JObject, JString, JValue};
+use jni::{JNIEnv, JavaVM};
+use jni::sys::{jboolean, jfloat, jint, jlong, jstring};
 use lazy_static::lazy_static;
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
 
 lazy_static! {
-    static ref PLAYER: Mutex<StreamPlayer> = Mutex::new(StreamPlayer::new());
+    static ref PLAYERS: Mutex<HashMap<i64, Arc<Mutex<StreamPlayer>>>> = Mutex::new(HashMap::new());
 }
 
-fn with_player<F, R>(f: F) -> Result<R, ErrorCode>
+static NEXT_HANDLE: AtomicI64 = AtomicI64::new(1);
+
+/// 创建播放器实例，返回句柄
+fn create_player() -> Result<i64, ErrorCode> {
+    let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+    PLAYERS
+        .lock()
+        .map_err(|_| ErrorCode::PlayerLockFailed)?
+        .insert(handle, Arc::new(Mutex::new(StreamPlayer::new())));
+    Ok(handle)
+}
+
+/// 销毁播放器实例
+fn destroy_player(handle: i64) -> Result<(), ErrorCode> {
+    let player = PLAYERS
+        .lock()
+        .map_err(|_| ErrorCode::PlayerLockFailed)?
+        .remove(&handle)
+        .ok_or(ErrorCode::PlayerNotInitialized)?;
+    // 在注册表锁之外释放播放器，避免等待播放线程时阻塞其他实例
+    drop(player);
+    Ok(())
+}
+
+fn with_player<F, R>(handle: i64, f: F) -> Result<R, ErrorCode>
 where
     F: FnOnce(&mut StreamPlayer) -> R,
 {
-    PLAYER
+    let player = PLAYERS
+        .lock()
+        .map_err(|_| ErrorCode::PlayerLockFailed)?
+        .get(&handle)
+        .cloned()
+        .ok_or(ErrorCode::PlayerNotInitialized)?;
+
+    player
         .lock()
         .map_err(|_| ErrorCode::PlayerLockFailed)
         .map(|mut player_guard| f(&mut player_guard))
 }
 
 fn throw_error(message: &str) {
-    let _ = get_env().throw_new("me/zhenxin/zmusic/player/JniPlayerException", message);
+    throw_error_with(get_env(), message);
+}
+
+fn throw_error_with(env: &mut JNIEnv, message: &str) {
+    let _ = env.throw_new("me/zhenxin/zmusic/player/JniPlayerException", message);
+}
+
+fn get_string(env: &mut JNIEnv, string: &JString) -> Option<String> {
+    env.get_string(string).ok().map(String::from)
+}
+
+/// Java 事件监听器，在事件分发线程中回调
+struct JniPlayerListener {
+    vm: JavaVM,
+    listener: GlobalRef,
+}
+
+impl PlayerListener for JniPlayerListener {
+    fn on_event(&mut self, event: PlayerEvent) {
+        let Ok(mut env) = self.vm.attach_current_thread_permanently() else {
+            return;
+        };
+        let listener = self.listener.as_obj();
+
+        let _ = match event {
+            PlayerEvent::StatusChanged(status) => {
+                env.call_method(listener, "onStatusChanged", "(I)V", &[JValue::Int(status as i32)])
+            }
+            PlayerEvent::PositionChanged(position) => {
+                env.call_method(listener, "onPositionChanged", "(J)V", &[JValue::Long(position as i64)])
+            }
+            PlayerEvent::TrackEnded => env.call_method(listener, "onTrackEnded", "()V", &[]),
+            PlayerEvent::Buffering(buffering) => {
+                env.call_method(listener, "onBuffering", "(Z)V", &[JValue::Bool(buffering as u8)])
+            }
+            PlayerEvent::Error(code, message) => match env.new_string(message) {
+                Ok(message) => env.call_method(
+                    listener,
+                    "onError",
+                    "(ILjava/lang/String;)V",
+                    &[JValue::Int(code), JValue::Object(&message)],
+                ),
+                Err(e) => Err(e),
+            },
+        };
+
+        // 监听器抛出的异常不能传播到分发线程
+        if env.exception_check().unwrap_or(false) {
+            let _ = env.exception_clear();
+        }
+    }
 }
 
 macro_rules! handle_void {
@@ -34,6 +126,41 @@ macro_rules! handle_void {
             return;
         }
     };
+    ($env:expr, $result:expr) => {
+        if let Err(error_code) = $result {
+            throw_error_with($env, &error_code.format_message());
+            return;
+        }
+    };
+}
+
+macro_rules! handle_result {
+    ($result:expr) => {
+        match $result {
+            Ok(Ok(_)) => {}
+            Ok(Err(player_error)) => {
+                throw_error(&player_error.format_message());
+                return;
+            }
+            Err(error_code) => {
+                throw_error(&error_code.format_message());
+                return;
+            }
+        }
+    };
+    ($env:expr, $result:expr) => {
+        match $result {
+            Ok(Ok(_)) => {}
+            Ok(Err(player_error)) => {
+                throw_error_with($env, &player_error.format_message());
+                return;
+            }
+            Err(error_code) => {
+                throw_error_with($env, &error_code.format_message());
+                return;
+            }
+        }
+    };
 }
 
 macro_rules! handle_getter {
@@ -48,56 +175,900 @@ macro_rules! handle_getter {
     };
 }
 
+macro_rules! invalid_parameter {
+    ($env:expr) => {{
+        throw_error_with($env, &ErrorCode::InvalidParameter.format_message());
+        return;
+    }};
+}
+
 jni_fn! { me.zhenxin.zmusic.player.JniPlayer =>
-    pub fn nativeResetPlayer<'local>() {
-        handle_void!(with_player(|player| player.reset()))
+    pub fn nativeCreatePlayer<'local>() -> i64 {
+        handle_getter!(create_player(), |handle| handle, 0)
     }
 
-    pub fn nativePlayUrl<'local>(url: String) {
-        match with_player(|player| player.play_url(&url)) {
-            Ok(Ok(_)) => {},
-            Ok(Err(_player_error)) => {
-                throw_error(&_player_error.format_message());
-            }
-            Err(error_code) => {
-                throw_error(&error_code.format_message());
-            }
+    pub fn nativeDestroyPlayer<'local>(handle: i64) {
+        handle_void!(destroy_player(handle))
+    }
+
+    pub fn nativeResetPlayer<'local>(handle: i64) {
+        handle_void!(with_player(handle, |player| player.reset()))
+    }
+
+    pub fn nativePause<'local>(handle: i64) {
+        handle_void!(with_player(handle, |player| player.pause()))
+    }
+
+    pub fn nativeResume<'local>(handle: i64) {
+        handle_void!(with_player(handle, |player| player.resume()))
+    }
+
+    pub fn nativeStop<'local>(handle: i64) {
+        handle_void!(with_player(handle, |player| player.stop()))
+    }
+
+    pub fn nativeClearLoop<'local>(handle: i64) {
+        handle_result!(with_player(handle, |player| player.set_loop_region(None)))
+    }
+
+    pub fn nativeClearQueue<'local>(handle: i64) {
+        handle_result!(with_player(handle, |player| player.clear_queue()))
+    }
+
+    pub fn nativeNext<'local>(handle: i64) {
+        handle_result!(with_player(handle, |player| player.play_next()))
+    }
+
+    pub fn nativePrevious<'local>(handle: i64) {
+        handle_result!(with_player(handle, |player| player.play_previous()))
+    }
+
+    pub fn nativeGetStatus<'local>(handle: i64) -> i32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.status as i32, -1)
+    }
+
+    pub fn nativeGetPosition<'local>(handle: i64) -> i64 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.current_time as i64, -1)
+    }
+
+    pub fn nativeGetDuration<'local>(handle: i64) -> i64 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.total_time.unwrap_or(0) as i64, -1)
+    }
+
+    pub fn nativeGetPositionMs<'local>(handle: i64) -> i64 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.current_time_ms as i64, -1)
+    }
+
+    pub fn nativeGetDurationMs<'local>(handle: i64) -> i64 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.total_time_ms.unwrap_or(0) as i64, -1)
+    }
+
+    pub fn nativeGetLastError<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.last_error.map(|error| error.format_message()).unwrap_or_default(), String::new())
+    }
+
+    pub fn nativeGetLastErrorCode<'local>(handle: i64) -> i32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.last_error.map(|error| error.code.code()).unwrap_or(0), -1)
+    }
+
+    pub fn nativeGetVolume<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.volume, -1.0)
+    }
+
+    pub fn nativeGetVolumeDb<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.volume_db(), f32::NAN)
+    }
+
+    pub fn nativeIsMuted<'local>(handle: i64) -> bool {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.muted, false)
+    }
+
+    pub fn nativeGetPreamp<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.preamp_db, f32::NAN)
+    }
+
+    pub fn nativeGetReplayGain<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.replay_gain_db, f32::NAN)
+    }
+
+    pub fn nativeGetLoudness<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_loudness()), |loudness| loudness.0.unwrap_or(f32::NAN), f32::NAN)
+    }
+
+    pub fn nativeGetNormalizationGain<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_loudness()), |loudness| loudness.1, f32::NAN)
+    }
+
+    pub fn nativeGetBalance<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.stereo.balance, f32::NAN)
+    }
+
+    pub fn nativeGetPan<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.stereo.pan, f32::NAN)
+    }
+
+    pub fn nativeIsMono<'local>(handle: i64) -> bool {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.stereo.mono, false)
+    }
+
+    pub fn nativeIsChannelsSwapped<'local>(handle: i64) -> bool {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.stereo.swap, false)
+    }
+
+    pub fn nativeGetAudioHosts<'local>() -> String {
+        hosts_to_json(&list_hosts())
+    }
+
+    pub fn nativeGetAudioHost<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.output_host.unwrap_or_default(), String::new())
+    }
+
+    // 设备选择只保存在内存中，Java 端保存后端和设备名称，下次启动时通过 nativeRestoreOutputDevice 恢复
+    pub fn nativeGetOutputDevice<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.output_device.unwrap_or_default(), String::new())
+    }
+
+    pub fn nativeGetFallbackDevice<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.fallback_device.unwrap_or_default(), String::new())
+    }
+
+    pub fn nativeIsEqualizerEnabled<'local>(handle: i64) -> bool {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.equalizer_enabled, false)
+    }
+
+    pub fn nativeGetEqualizer<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_equalizer()), |settings| settings.to_json(), String::new())
+    }
+
+    pub fn nativeGetEqualizerPresets<'local>(handle: i64) -> String {
+        handle_getter!(with_player(handle, |player| player.get_equalizer_presets()), |names| serde_json::to_string(&names).unwrap_or_default(), String::new())
+    }
+
+    pub fn nativeGetFadeDuration<'local>(handle: i64) -> i64 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.fade_duration as i64, -1)
+    }
+
+    pub fn nativeGetSpeed<'local>(handle: i64) -> f32 {
+        handle_getter!(with_player(handle, |player| player.get_player_info()), |info| info.speed, -1.0)
+    }
+
+    pub fn nativeGetQueueSize<'local>(handle: i64) -> i32 {
+        handle_getter!(with_player(handle, |player| player.get_queue()), |queue| queue.len() as i32, -1)
+    }
+
+    pub fn nativeGetQueueIndex<'local>(handle: i64) -> i32 {
+        handle_getter!(with_player(handle, |player| player.get_queue()), |queue| queue.current_index().map(|index| index as i32).unwrap_or(-1), -1)
+    }
+}
+
+// jni_fn! 宏展开多个参数时会生成重复的逗号，多参数或需要 JNIEnv 的方法直接通过 jni 导出
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativePlayUrl<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    url: JString<'local>,
+) {
+    let Some(url) = get_string(&mut env, &url) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.play_url(&url)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativePlayUrlWithLoop<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    url: JString<'local>,
+    loop_start: jlong,
+    loop_end: jlong,
+    use_loop_tags: jboolean,
+) {
+    let Some(url) = get_string(&mut env, &url) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    // 起点为负数时不设置循环区间，终点为负数时循环到轨道末尾
+    let loop_region = (loop_start >= 0).then(|| LoopRegion::Samples {
+        start: loop_start as u64,
+        end: (loop_end >= 0).then_some(loop_end as u64),
+    });
+    let options = PlayOptions {
+        loop_region,
+        use_loop_tags: use_loop_tags != 0,
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.play_url_with_options(&url, options)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetAbRepeat<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    start_ms: jlong,
+    end_ms: jlong,
+) {
+    if start_ms < 0 || end_ms <= start_ms {
+        invalid_parameter!(&mut env);
+    }
+    handle_result!(&mut env, with_player(handle, |player| player.set_ab_repeat(start_ms as u64, end_ms as u64)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetRepeatTrack<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    repeat_track: jboolean,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_repeat_track(repeat_track != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSeek<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    position: jlong,
+) {
+    if position < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_result!(&mut env, with_player(handle, |player| player.seek(position as u64)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetVolume<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    volume: jfloat,
+) {
+    if volume < 0.0 || volume > 1.0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_void!(&mut env, with_player(handle, |player| player.set_volume(volume)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetVolumeDb<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    volume_db: jfloat,
+) {
+    // 负无穷表示静音
+    if volume_db.is_nan() || volume_db > 0.0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_void!(&mut env, with_player(handle, |player| player.set_volume_db(volume_db)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetVolumeCurve<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    curve: jint,
+) {
+    let Some(curve) = VolumeCurve::from_code(curve) else {
+        invalid_parameter!(&mut env);
+    };
+    handle_void!(&mut env, with_player(handle, |player| player.set_volume_curve(curve)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetReplayGainMode<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    mode: jint,
+) {
+    let Some(mode) = ReplayGainMode::from_code(mode) else {
+        invalid_parameter!(&mut env);
+    };
+    handle_void!(&mut env, with_player(handle, |player| player.set_replay_gain_mode(mode)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetMuted<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    muted: jboolean,
+) {
+    handle_void!(&mut env, with_player(handle, |player| player.set_muted(muted != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetPreamp<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    preamp_db: jfloat,
+) {
+    if !(0.0..=MAX_PREAMP_DB).contains(&preamp_db) {
+        invalid_parameter!(&mut env);
+    }
+    handle_void!(&mut env, with_player(handle, |player| player.set_preamp(preamp_db)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeFadeVolume<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    volume: jfloat,
+    duration_ms: jlong,
+) {
+    if !(0.0..=1.0).contains(&volume) || duration_ms < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_void!(&mut env, with_player(handle, |player| player.fade_volume(volume, duration_ms as u64)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetFadeDuration<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    duration_ms: jlong,
+) {
+    if duration_ms < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_void!(&mut env, with_player(handle, |player| player.set_fade_duration(duration_ms as u64)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeGetOutputDevices<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    host: JString<'local>,
+) -> jstring {
+    let Some(host) = get_string(&mut env, &host) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return std::ptr::null_mut();
+    };
+    // 空名称表示默认后端
+    let host = Some(host).filter(|host| !host.is_empty());
+    let devices = match list_output_devices(host.as_deref()) {
+        Ok(devices) => devices,
+        Err(e) => {
+            throw_error_with(&mut env, &PlayerError::from(e).format_message());
+            return std::ptr::null_mut();
+        }
+    };
+    match env.new_string(devices_to_json(&devices)) {
+        Ok(json) => json.into_raw(),
+        Err(_) => {
+            throw_error_with(&mut env, &ErrorCode::JniObjectCreationFailed.format_message());
+            std::ptr::null_mut()
         }
     }
+}
 
-    pub fn nativePause<'local>() {
-        handle_void!(with_player(|player| player.pause()))
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetAudioHost<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    name: JString<'local>,
+) {
+    let Some(name) = get_string(&mut env, &name) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    // 空名称表示默认后端
+    let name = Some(name).filter(|name| !name.is_empty());
+    handle_result!(&mut env, with_player(handle, |player| player.set_output_host(name)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetOutputDevice<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    name: JString<'local>,
+) {
+    let Some(name) = get_string(&mut env, &name) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    // 空名称表示默认设备
+    let name = Some(name).filter(|name| !name.is_empty());
+    handle_result!(&mut env, with_player(handle, |player| player.set_output_device(name)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetFallbackDevice<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    name: JString<'local>,
+) {
+    let Some(name) = get_string(&mut env, &name) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    // 空名称表示不使用备用设备
+    let name = Some(name).filter(|name| !name.is_empty());
+    handle_result!(&mut env, with_player(handle, |player| player.set_fallback_device(name)))
+}
+
+/// 恢复 Java 端保存的后端、输出设备和备用设备名称，空名称表示默认；设备当前不可用时不报错，播放时使用备用或默认设备
+///
+/// 设备选择不会由播放器持久化，应保存 nativeGetAudioHost、nativeGetOutputDevice 和 nativeGetFallbackDevice
+/// 返回的名称，设备列表中的ID在设备插拔后会变化，不应保存
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeRestoreOutputDevice<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    host: JString<'local>,
+    device: JString<'local>,
+    fallback_device: JString<'local>,
+) {
+    let (Some(host), Some(device), Some(fallback_device)) = (
+        get_string(&mut env, &host),
+        get_string(&mut env, &device),
+        get_string(&mut env, &fallback_device),
+    ) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    let host = Some(host).filter(|host| !host.is_empty());
+    let device = Some(device).filter(|device| !device.is_empty());
+    let fallback_device = Some(fallback_device).filter(|device| !device.is_empty());
+    handle_result!(
+        &mut env,
+        with_player(handle, |player| player.restore_output_device(host, device, fallback_device))
+    )
+}
+
+/// 按设备列表中的ID选择设备，保存的是设备名称；ID 在设备插拔后会变化，持久化时应保存 nativeGetOutputDevice 返回的名称
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetOutputDeviceById<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    id: jint,
+) {
+    if id < 0 {
+        invalid_parameter!(&mut env);
     }
+    handle_result!(&mut env, with_player(handle, |player| player.set_output_device_by_id(id as usize)))
+}
 
-    pub fn nativeResume<'local>() {
-        handle_void!(with_player(|player| player.resume()))
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetNoiseShaping<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    noise_shaping: jboolean,
+) {
+    handle_void!(&mut env, with_player(handle, |player| player.set_noise_shaping(noise_shaping != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetSpeed<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    speed: jfloat,
+) {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        invalid_parameter!(&mut env);
     }
+    handle_void!(&mut env, with_player(handle, |player| player.set_speed(speed)))
+}
 
-    pub fn nativeStop<'local>() {
-        handle_void!(with_player(|player| player.stop()))
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetSpeedMode<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    mode: jint,
+) {
+    let Some(mode) = SpeedMode::from_code(mode) else {
+        invalid_parameter!(&mut env);
+    };
+    handle_void!(&mut env, with_player(handle, |player| player.set_speed_mode(mode)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetPositionInterval<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    interval_ms: jlong,
+) {
+    if interval_ms < 0 {
+        invalid_parameter!(&mut env);
     }
+    handle_void!(&mut env, with_player(handle, |player| player.set_position_interval(interval_ms as u64)))
+}
 
-    pub fn nativeSetVolume<'local>(volume: f32) {
-        if volume < 0.0 || volume > 1.0 {
-            throw_error(&ErrorCode::InvalidParameter.format_message());
-            return;
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeEnqueue<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    url: JString<'local>,
+) {
+    let Some(url) = get_string(&mut env, &url) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.enqueue(&url)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeRemoveFromQueue<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    index: jint,
+) {
+    if index < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_result!(&mut env, with_player(handle, |player| player.remove_from_queue(index as usize)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativePlayIndex<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    index: jint,
+) {
+    if index < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_result!(&mut env, with_player(handle, |player| player.play_index(index as usize)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeMoveInQueue<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    from_index: jint,
+    to_index: jint,
+) {
+    if from_index < 0 || to_index < 0 {
+        invalid_parameter!(&mut env);
+    }
+    handle_result!(
+        &mut env,
+        with_player(handle, |player| player.move_in_queue(from_index as usize, to_index as usize))
+    )
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetCrossfade<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    duration_ms: jlong,
+    curve: jint,
+) {
+    let curve = match CrossfadeCurve::from_code(curve) {
+        Some(curve) if duration_ms >= 0 => curve,
+        _ => invalid_parameter!(&mut env),
+    };
+    handle_void!(&mut env, with_player(handle, |player| player.set_crossfade(duration_ms as u64, curve)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetLoudnessNormalization<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    enabled: jboolean,
+    target_lufs: jfloat,
+) {
+    handle_result!(
+        &mut env,
+        with_player(handle, |player| player.set_loudness_normalization(enabled != 0, target_lufs))
+    )
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeAnalyzeLoudness<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    url: JString<'local>,
+) -> jstring {
+    let Some(url) = get_string(&mut env, &url) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return std::ptr::null_mut();
+    };
+    let stats = match PreparedTrack::open(&url).and_then(|mut track| track.analyze_loudness()) {
+        Ok(stats) => stats,
+        Err(player_error) => {
+            throw_error_with(&mut env, &player_error.format_message());
+            return std::ptr::null_mut();
+        }
+    };
+    match env.new_string(stats.to_json()) {
+        Ok(json) => json.into_raw(),
+        Err(_) => {
+            throw_error_with(&mut env, &ErrorCode::JniObjectCreationFailed.format_message());
+            std::ptr::null_mut()
         }
-        handle_void!(with_player(|player| player.set_volume(volume)))
     }
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetBalance<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    balance: jfloat,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_balance(balance)))
+}
 
-    pub fn nativeGetStatus<'local>() -> i32 {
-        handle_getter!(with_player(|player| player.get_player_info()), |info| info.status as i32, -1)
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetPan<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    pan: jfloat,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_pan(pan)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetChannelGain<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    channel: jint,
+    gain_db: jfloat,
+) {
+    if channel < 0 {
+        invalid_parameter!(&mut env);
     }
+    handle_result!(
+        &mut env,
+        with_player(handle, |player| player.set_channel_gain(channel as usize, gain_db))
+    )
+}
+
+/// 单声道混音只作用于前左和前右声道，多声道输出时中置、低频和环绕声道保持不变
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetMono<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    mono: jboolean,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_mono(mono != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetSwapChannels<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    swap: jboolean,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_swap_channels(swap != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetEqualizerEnabled<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    enabled: jboolean,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_equalizer_enabled(enabled != 0)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetEqualizerMode<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    mode: jint,
+) {
+    let Some(mode) = EqMode::from_code(mode) else {
+        invalid_parameter!(&mut env);
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.set_equalizer_mode(mode)))
+}
 
-    pub fn nativeGetPosition<'local>() -> i64 {
-        handle_getter!(with_player(|player| player.get_player_info()), |info| info.current_time as i64, -1)
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetEqualizerBandGain<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    index: jint,
+    gain_db: jfloat,
+) {
+    if index < 0 {
+        invalid_parameter!(&mut env);
     }
+    handle_result!(
+        &mut env,
+        with_player(handle, |player| player.set_equalizer_band_gain(index as usize, gain_db))
+    )
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetEqualizerBand<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    index: jint,
+    filter_type: jint,
+    frequency: jfloat,
+    gain_db: jfloat,
+    q: jfloat,
+) {
+    let filter_type = match FilterType::from_code(filter_type) {
+        Some(filter_type) if index >= 0 => filter_type,
+        _ => invalid_parameter!(&mut env),
+    };
+    let band = EqBand {
+        filter_type,
+        frequency,
+        gain_db,
+        q,
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.set_equalizer_band(index as usize, band)))
+}
 
-    pub fn nativeGetDuration<'local>() -> i64 {
-        handle_getter!(with_player(|player| player.get_player_info()), |info| info.total_time.unwrap_or(0) as i64, -1)
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeRemoveEqualizerBand<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    index: jint,
+) {
+    if index < 0 {
+        invalid_parameter!(&mut env);
     }
+    handle_result!(&mut env, with_player(handle, |player| player.remove_equalizer_band(index as usize)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetEqualizerPreamp<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    preamp_db: jfloat,
+) {
+    handle_result!(&mut env, with_player(handle, |player| player.set_equalizer_preamp(preamp_db)))
+}
 
-    pub fn nativeGetVolume<'local>() -> f32 {
-        handle_getter!(with_player(|player| player.get_player_info()), |info| info.volume, -1.0)
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeLoadEqualizerPreset<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    name: JString<'local>,
+) {
+    let Some(name) = get_string(&mut env, &name) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.load_equalizer_preset(&name)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeImportEqualizerPreset<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    json: JString<'local>,
+) {
+    let Some(json) = get_string(&mut env, &json) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return;
+    };
+    handle_result!(&mut env, with_player(handle, |player| player.import_equalizer_preset(&json)))
+}
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeExportEqualizerPreset<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    name: JString<'local>,
+) -> jstring {
+    let Some(name) = get_string(&mut env, &name) else {
+        throw_error_with(&mut env, &ErrorCode::JniStringConversionFailed.format_message());
+        return std::ptr::null_mut();
+    };
+    let json = match with_player(handle, |player| player.export_equalizer_preset(&name)) {
+        Ok(Ok(json)) => json,
+        Ok(Err(error_code)) | Err(error_code) => {
+            throw_error_with(&mut env, &error_code.format_message());
+            return std::ptr::null_mut();
+        }
+    };
+    match env.new_string(json) {
+        Ok(json) => json.into_raw(),
+        Err(_) => {
+            throw_error_with(&mut env, &ErrorCode::JniObjectCreationFailed.format_message());
+            std::ptr::null_mut()
+        }
     }
 }
+
+#[unsafe(no_mangle)]
+#[allow(non_snake_case)]
+pub extern "system" fn Java_me_zhenxin_zmusic_player_JniPlayer_nativeSetListener<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject<'local>,
+    handle: jlong,
+    listener: JObject<'local>,
+) {
+    // 传入 null 时移除监听器
+    let listener: Option<Box<dyn PlayerListener>> = if listener.is_null() {
+        None
+    } else {
+        let (vm, listener) = match (env.get_java_vm(), env.new_global_ref(&listener)) {
+            (Ok(vm), Ok(listener)) => (vm, listener),
+            _ => {
+                throw_error_with(&mut env, &ErrorCode::JniObjectCreationFailed.format_message());
+                return;
+            }
+        };
+        Some(Box::new(JniPlayerListener { vm, listener }))
+    };
+
+    handle_void!(&mut env, with_player(handle, |player| player.set_listener(listener)))
+}
@@ -1,103 +1,262 @@
 use crate::{
-    audio::create_audio_output,
-    player::{NetworkMediaSource, PlayerInfo, Status},
-    error_codes::ErrorCode,
+    audio::{
+        create_audio_output,
+        crossfade::{CrossfadeCurve, Crossfader},
+        device::{device_name_by_id, find_host, find_output_device},
+        dsp::AudioProcessor,
+        equalizer::{
+            EqBand, EqMode, EqPreset, EqSettings, Equalizer, EqualizerControl, builtin_preset,
+            builtin_presets,
+        },
+        gain::{DEFAULT_FADE_MS, GainRamp, VOLUME_RAMP_MS, VolumeCurve, db_to_gain},
+        loudness::{
+            DEFAULT_TARGET_LUFS, LoudnessControl, LoudnessNormalizer, MAX_TARGET_LUFS,
+            MIN_TARGET_LUFS,
+        },
+        output::{AudioOutput, OutputControl, recover_audio_output},
+        replaygain::{ReplayGainMode, apply_gain},
+        stereo::StereoMixer,
+        stretch::SpeedMode,
+    },
+    player::{
+        EventDispatcher, LoopRegion, PlayOptions, PlayQueue, PlayerEvent, PlayerInfo,
+        PlayerListener, PreparedTrack, RemoteInfo, Status,
+    },
+    error_codes::{ErrorCode, PlayerError},
 };
+use std::collections::HashMap;
 use std::thread;
-use std::time::{Duration as StdDuration, Instant};
 use symphonia::core::{
-    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    audio::{AsAudioBufferRef, AudioBufferRef, Signal, SignalSpec},
     errors::Error,
-    formats::{FormatOptions, FormatReader},
-    io::MediaSourceStream,
-    meta::MetadataOptions,
-    probe::Hint,
+    formats::{SeekMode, SeekTo},
+    units::Time,
 };
 
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, LockResult, Mutex, MutexGuard};
 
 // 类型别名，简化复杂的类型嵌套
-type PlayerInfoArc = Arc<Mutex<PlayerInfo>>;
+type PlayerInfoArc = Arc<SharedInfo>;
+type PlayQueueArc = Arc<Mutex<PlayQueue>>;
+type PreloadHandle = thread::JoinHandle<Option<PreparedTrack>>;
+
+/// 播放器信息，状态变化时唤醒等待中的播放线程
+struct SharedInfo {
+    info: Mutex<PlayerInfo>,
+    changed: Condvar,
+    /// 输出控制，暂停时由调用线程直接停止音频回调读取
+    output: Arc<OutputControl>,
+    /// 响度归一化控制，切换轨道时重新测量
+    loudness: Arc<LoudnessControl>,
+}
+
+impl SharedInfo {
+    fn new() -> Self {
+        Self {
+            info: Mutex::new(PlayerInfo::new()),
+            changed: Condvar::new(),
+            output: Arc::new(OutputControl::new()),
+            loudness: Arc::new(LoudnessControl::new()),
+        }
+    }
+
+    fn lock(&self) -> LockResult<MutexGuard<'_, PlayerInfo>> {
+        self.info.lock()
+    }
+
+    /// 通知播放线程状态已变化
+    fn notify(&self) {
+        self.changed.notify_all();
+    }
+
+    /// 暂停期间阻塞，返回恢复后的状态；暂停期间有定位请求时返回 Paused 以便先处理定位
+    fn wait_while_paused(&self) -> Status {
+        let info = self
+            .changed
+            .wait_while(self.info.lock().unwrap(), |info| {
+                info.status() == Status::Paused && info.seek_position.is_none()
+            })
+            .unwrap();
+        info.status()
+    }
+}
+
+/// 正在交叉淡化的下一首
+struct FadingTrack {
+    track: PreparedTrack,
+    crossfader: Crossfader,
+    /// 下一首的回放增益
+    replay_gain: GainRamp,
+}
+
+impl FadingTrack {
+    /// 解码下一首直到缓存足够混合指定帧数，规格不一致时返回 false
+    fn fill(&mut self, frames: usize) -> bool {
+        while self.crossfader.buffered_frames() < frames {
+            let packet = match self.track.reader.next_packet() {
+                Ok(packet) => packet,
+                // 下一首已经读完，不足部分以静音混合
+                Err(_) => return true,
+            };
+
+            if packet.track_id() != self.track.track_id {
+                continue;
+            }
+
+            match self.track.decoder.decode(&packet) {
+                Ok(decoded) => {
+                    if *decoded.spec() != self.crossfader.spec() {
+                        return false;
+                    }
+                    let mut buf = decoded.make_equivalent::<f32>();
+                    decoded.convert(&mut buf);
+                    apply_gain(&mut buf, &mut self.replay_gain);
+                    self.crossfader.push_incoming(buf.as_audio_buffer_ref());
+                }
+                Err(Error::DecodeError(_)) => continue,
+                Err(_) => return true,
+            }
+        }
+        true
+    }
+}
 
 /// 播放器
 pub struct StreamPlayer {
     player_info: PlayerInfoArc,
+    queue: PlayQueueArc,
+    events: EventDispatcher,
     playback_thread: Option<thread::JoinHandle<()>>,
+    /// 均衡器设置
+    equalizer: Arc<EqualizerControl>,
+    /// 均衡器在处理链中的ID
+    equalizer_id: u64,
+    /// 响度归一化在处理链中的ID
+    loudness_id: u64,
+    /// 立体声处理器在处理链中的ID
+    stereo_id: u64,
+    /// 用户保存的均衡器预设
+    equalizer_presets: HashMap<String, EqPreset>,
 }
 
 impl StreamPlayer {
     /// 创建播放器
     pub fn new() -> Self {
+        let player_info = Arc::new(SharedInfo::new());
+        let equalizer = Arc::new(EqualizerControl::new());
+
+        // 均衡器位于音量处理器之前，默认旁路
+        let equalizer_id = {
+            let mut chain = player_info.output.chain();
+            let id = chain.insert(0, Box::new(Equalizer::new(Arc::clone(&equalizer))));
+            chain.set_bypassed(id, true);
+            id
+        };
+
+        // 响度归一化位于均衡器之前，默认旁路
+        let loudness_id = {
+            let mut chain = player_info.output.chain();
+            let loudness = LoudnessNormalizer::new(Arc::clone(&player_info.loudness));
+            let id = chain.insert(0, Box::new(loudness));
+            chain.set_bypassed(id, true);
+            id
+        };
+
+        // 立体声处理器紧接在音量处理器之前
+        let stereo_id = {
+            let mut chain = player_info.output.chain();
+            let index = chain.len() - 1;
+            chain.insert(index, Box::new(StereoMixer::new()))
+        };
+
         Self {
-            player_info: Arc::new(Mutex::new(PlayerInfo::new())),
+            player_info,
+            queue: Arc::new(Mutex::new(PlayQueue::new())),
+            events: EventDispatcher::new(),
             playback_thread: None,
+            equalizer,
+            equalizer_id,
+            loudness_id,
+            stereo_id,
+            equalizer_presets: HashMap::new(),
         }
     }
 
-    /// 验证URL
-    fn validate_url(&self, url: &str) -> Result<(), ErrorCode> {
+    /// 验证URL，返回响应头信息供打开轨道时复用
+    fn validate_url(&self, url: &str) -> Result<RemoteInfo, ErrorCode> {
         // 检查URL格式
         if !url.starts_with("http://") && !url.starts_with("https://") {
             return Err(ErrorCode::UrlUnsupportedProtocol);
         }
 
         // 尝试建立连接并获取响应头
-        let response = match ureq::head(url)
-            .timeout(std::time::Duration::from_secs(3))
-            .call() {
-                Ok(resp) => resp,
-                Err(ureq::Error::Status(status, _)) => {
-                    return if status == 404 {
-                        Err(ErrorCode::MediaNotFound)
-                    } else {
-                        Err(ErrorCode::HttpError)
-                    };
-                }
-                Err(ureq::Error::Transport(_)) => {
-                    return Err(ErrorCode::ConnectionTimeout);
-                }
-            };
- 
-        // 检查状态码
-        let status = response.status();
-        
-        if status < 200 || status >= 300 {
-            return Err(ErrorCode::HttpError);
-        }
-
-        Ok(())
+        RemoteInfo::probe(url)
     }
 
     /// 播放
     pub fn play_url(&mut self, url: &str) -> Result<i32, ErrorCode> {
+        self.play_url_with_options(url, PlayOptions::default())
+    }
+
+    /// 按播放选项播放
+    pub fn play_url_with_options(&mut self, url: &str, options: PlayOptions) -> Result<i32, ErrorCode> {
+        self.start_playback(url, false, options)
+    }
+
+    /// 启动播放线程
+    fn start_playback(
+        &mut self,
+        url: &str,
+        auto_advance: bool,
+        options: PlayOptions,
+    ) -> Result<i32, ErrorCode> {
+        if options.loop_region.is_some_and(|region| !region.is_valid()) {
+            return Err(ErrorCode::InvalidParameter);
+        }
+
         // 先停止当前播放
         self.stop().map_err(|_| ErrorCode::PlayerOperationFailed)?;
 
         // 设置加载状态
         {
             let mut info = self.player_info.lock().unwrap();
-            info.set_status(Status::Loading);
+            Self::update_status(&mut info, &self.events, Status::Loading);
+            info.set_last_error(None);
             info.set_current_time(0); // 重置播放时间
+            info.set_seek_position(None);
+            info.set_seekable(false);
+            info.set_loop_region(options.loop_region);
+            info.set_use_loop_tags(options.use_loop_tags);
         }
 
         // 验证网络文件
-        self.validate_url(url)?;
+        let remote_info = match self.validate_url(url) {
+            Ok(remote_info) => remote_info,
+            Err(error_code) => {
+                let mut info = self.player_info.lock().unwrap();
+                info.set_last_error(Some(PlayerError::new(error_code, format!("Failed to open {}", url))));
+                Self::update_status(&mut info, &self.events, Status::Error);
+                return Err(error_code);
+            }
+        };
 
         // 在新线程中播放
         let player_info = Arc::clone(&self.player_info);
+        let queue = Arc::clone(&self.queue);
+        let events = self.events.clone();
         let url = url.to_string();
 
         let handle = thread::spawn(move || {
-            let result = Self::play_internal(&url, &player_info);
-
-            match result {
-                Ok(_) => {
-                    let mut info = player_info.lock().unwrap();
-                    info.set_status(Status::Stopped);
-                }
-                Err(_) => {
-                    // 播放错误时，保持当前状态，错误通过返回值处理
-                    // 不再设置Status::Error，因为已从Status枚举中移除
+            // 播放错误时记录错误并切换到错误状态
+            let result =
+                Self::play_internal(&url, remote_info, &player_info, &queue, &events, auto_advance);
+            if let Err(error) = result {
+                let mut info = player_info.lock().unwrap();
+                // 用户已停止时忽略停止过程中产生的错误
+                if info.status() != Status::Stopped {
+                    events.emit(PlayerEvent::Error(error.code.code(), error.message.clone()));
+                    info.set_last_error(Some(error));
+                    Self::update_status(&mut info, &events, Status::Error);
                 }
             }
         });
@@ -109,106 +268,249 @@ impl StreamPlayer {
     /// 播放实现
     fn play_internal(
         url: &str,
+        remote_info: RemoteInfo,
         player_info: &PlayerInfoArc,
-    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
-        let mut hint = Hint::new();
-        if url.ends_with(".mp3") {
-            hint.with_extension("mp3");
-        } else if url.ends_with(".flac") {
-            hint.with_extension("flac");
-        }
+        queue: &PlayQueueArc,
+        events: &EventDispatcher,
+        auto_advance: bool,
+    ) -> std::result::Result<i32, PlayerError> {
+        let mut track = Self::open_track(url, Some(remote_info), events)?;
 
-        let source = Box::new(NetworkMediaSource::new(url.to_string())?);
-        let mss = MediaSourceStream::new(source, Default::default());
+        // 音频输出在轨道之间复用，保证前一首的最后一个采样紧接着下一首的第一个采样
+        let mut audio_output = None;
+        // 从上一首交叉淡化过来时，剩余的淡入
+        let mut fade_in = None;
 
-        let probed = symphonia::default::get_probe()
-            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())?;
+        let result = loop {
+            // 在后台预加载下一首
+            let mut preload = if auto_advance {
+                let next_url = queue.lock().unwrap().peek_next().map(str::to_string);
+                next_url.map(|next_url| thread::spawn(move || PreparedTrack::open(&next_url).ok()))
+            } else {
+                None
+            };
 
-        let mut reader = probed.format;
+            {
+                let mut info = player_info.lock().unwrap();
+                if info.status() == Status::Stopped {
+                    break Ok(0);
+                }
+                info.set_total_time_ms(track.total_time_ms);
+                info.set_seekable(track.seekable);
+                info.set_current_time(0);
+                if info.status() == Status::Loading {
+                    Self::update_status(&mut info, events, Status::Playing);
+                }
+            }
 
-        // 获取总时长
-        if let Some(track) = reader.tracks().iter().find(|t| t.codec_params.codec != CODEC_TYPE_NULL) {
-            let params = &track.codec_params;
-            let total_time = params.n_frames.map(|frames| frames / params.sample_rate.unwrap_or(44100) as u64);
+            let fading = match Self::play_track_internal(
+                &mut track,
+                player_info,
+                queue,
+                events,
+                &mut audio_output,
+                &mut preload,
+                fade_in.take(),
+            ) {
+                Ok(fading) => fading,
+                Err(e) => break Err(e),
+            };
 
-            let mut info = player_info.lock().unwrap();
-            info.set_total_time(total_time);
-        }
+            // 用户主动停止，不再自动切换
+            if player_info.lock().unwrap().status() == Status::Stopped {
+                break Ok(0);
+            }
 
-        // 设置播放状态
-        {
-            let mut info = player_info.lock().unwrap();
-            info.set_status(Status::Playing);
-        }
+            events.emit(PlayerEvent::TrackEnded);
+
+            // 自动切换到队列中的下一首
+            let next_url = if auto_advance {
+                queue.lock().unwrap().next_entry()
+            } else {
+                None
+            };
+
+            let next_url = match next_url {
+                Some(next_url) => next_url,
+                None => {
+                    let mut info = player_info.lock().unwrap();
+                    Self::update_status(&mut info, events, Status::Stopped);
+                    break Ok(0);
+                }
+            };
+
+            // 正在交叉淡化的下一首直接接着播放
+            if let Some(fading) = fading.filter(|fading| fading.track.url == next_url) {
+                track = fading.track;
+                fade_in = Some(fading.crossfader);
+                continue;
+            }
 
-        // 开始时间跟踪
-        let start_time = Instant::now();
-        let last_update = Instant::now();
+            // 队列在预加载期间可能被修改，只有URL一致时才使用预加载的轨道
+            let preloaded = preload
+                .and_then(|handle| handle.join().ok().flatten())
+                .filter(|prepared| prepared.url == next_url);
 
-        Self::play_track_internal(&mut reader, player_info, start_time, last_update)
+            track = match preloaded {
+                Some(prepared) => prepared,
+                None => match Self::open_track(&next_url, None, events) {
+                    Ok(prepared) => prepared,
+                    Err(e) => break Err(e),
+                },
+            };
+        };
+
+        // 清理音频输出
+        if let Some(mut audio_output) = audio_output {
+            audio_output.flush();
+        }
+
+        result
     }
 
     /// 轨道播放
     fn play_track_internal(
-        reader: &mut Box<dyn FormatReader>,
+        track: &mut PreparedTrack,
         player_info: &PlayerInfoArc,
-        start_time: Instant,
-        mut last_update: Instant,
-    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
-        let decode_opts = &DecoderOptions::default();
-
-        let track = reader
-            .tracks()
-            .iter()
-            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL);
-        let track_id = match track {
-            Some(track) => track.id,
-            _ => return Ok(0),
-        };
+        queue: &PlayQueueArc,
+        events: &EventDispatcher,
+        audio_output: &mut Option<AudioOutput>,
+        preload: &mut Option<PreloadHandle>,
+        mut fade_in: Option<Crossfader>,
+    ) -> std::result::Result<Option<FadingTrack>, PlayerError> {
+        let track_id = track.track_id;
+        let n_frames = track.n_frames;
+        let time_base = track.time_base;
+        let loop_tags = track.loop_tags;
+        let replay_gain_tags = track.replay_gain;
+        let reader = &mut track.reader;
+        let decoder = &mut track.decoder;
 
-        let mut decoder =
-            symphonia::default::get_codecs().make(&track.unwrap().codec_params, decode_opts)?;
-        let mut audio_output = None;
+        // 正在淡入的下一首
+        let mut fading: Option<FadingTrack> = None;
+
+        // 最近解码的音频结束时间(秒)
+        let mut decoded_time: Option<f64> = None;
+
+        // 到达循环结尾后待跳回的起点，以及刚跳回起点时需要裁掉起点之前的帧
+        let mut loop_back: Option<LoopRegion> = None;
+        let mut after_loop = false;
+
+        // 回放增益，切换模式时渐变
+        let mut replay_gain: Option<GainRamp> = None;
+
+        // 输出播放到新轨道时响度计重新测量，上一首留在缓冲区中的音频不计入
+        match audio_output.as_mut() {
+            Some(output) => output.mark_track_start(),
+            None => player_info.output.chain().start_track(),
+        }
+
+        // 先输出交叉淡化期间已解码的部分
+        if let Some(buf) = fade_in.as_mut().and_then(Crossfader::drain) {
+            Self::write_output(audio_output, player_info, buf.as_audio_buffer_ref())?;
+        }
 
         loop {
-            // 检查停止状态
-            {
-                let info = player_info.lock().unwrap();
+            // 检查停止状态，并取得当前生效的循环区间和回放增益
+            let (active_loop, replay_gain_db) = {
+                let mut info = player_info.lock().unwrap();
                 if info.status() == Status::Stopped {
                     break;
                 }
+                let replay_gain_mode = info.replay_gain_mode();
+                let replay_gain_db = replay_gain_tags.gain_db(replay_gain_mode);
+                info.set_replay_gain_db(replay_gain_db);
+                // 已应用回放增益的轨道不再归一化
+                player_info.loudness.set_track_tagged(
+                    replay_gain_mode != ReplayGainMode::Off && !replay_gain_tags.is_empty(),
+                );
+                (info.active_loop(loop_tags), replay_gain_db)
+            };
+
+            let target_gain = db_to_gain(replay_gain_db);
+            match replay_gain.as_mut() {
+                Some(ramp) if ramp.target() != target_gain => {
+                    let rate = decoder.codec_params().sample_rate.unwrap_or(0);
+                    ramp.set_target(target_gain, (VOLUME_RAMP_MS * rate as u64 / 1000) as usize);
+                }
+                Some(_) => {}
+                None => replay_gain = Some(GainRamp::new(target_gain)),
             }
 
-            // 检查暂停状态
-            loop {
-                let status = {
-                    let info = player_info.lock().unwrap();
-                    info.status()
-                };
+            // 更新播放位置
+            Self::update_position(player_info, events, decoded_time, audio_output);
 
-                if status == Status::Paused {
-                    thread::sleep(StdDuration::from_millis(10));
-                    let status = {
-                        let info = player_info.lock().unwrap();
-                        info.status()
-                    };
-                    if status == Status::Stopped {
-                        return Ok(0);
+            // 暂停时停止输出设备，等待恢复或停止
+            let paused = {
+                let info = player_info.lock().unwrap();
+                info.status() == Status::Paused
+            };
+            if paused {
+                if let Some(output) = audio_output.as_mut() {
+                    output.pause();
+                }
+                match player_info.wait_while_paused() {
+                    Status::Stopped => return Ok(None),
+                    // 暂停期间定位，处理定位后继续暂停
+                    Status::Paused => {}
+                    _ => {
+                        if let Some(output) = audio_output.as_mut() {
+                            output.resume()?;
+                        }
                     }
-                } else {
-                    break;
                 }
             }
 
-            // 更新播放时间（每秒更新一次）
-            let now = Instant::now();
-            if now.duration_since(last_update).as_secs() >= 1 {
-                let elapsed = now.duration_since(start_time).as_secs();
-                {
-                    let mut info = player_info.lock().unwrap();
-                    info.set_current_time(elapsed);
+            // 处理定位请求
+            let seek_position = {
+                let mut info = player_info.lock().unwrap();
+                info.take_seek_position()
+            };
+            if let Some(position) = seek_position {
+                let seek_to = SeekTo::Time {
+                    time: Time::new(position / 1000, (position % 1000) as f64 / 1000.0),
+                    track_id: Some(track_id),
+                };
+                match reader.seek(SeekMode::Accurate, seek_to) {
+                    Ok(_) => {
+                        decoder.reset();
+                        // 定位后放弃正在进行的淡化和循环跳转
+                        fading = None;
+                        fade_in = None;
+                        decoded_time = None;
+                        loop_back = None;
+                        after_loop = false;
+                        // 丢弃定位前已写入输出的音频
+                        if let Some(output) = audio_output.as_mut() {
+                            output.discard_buffered();
+                        }
+                        let mut info = player_info.lock().unwrap();
+                        info.set_current_time_ms(position);
+                    }
+                    // 定位失败时从原位置继续播放，记录错误并通知监听器
+                    Err(e) => {
+                        let error = PlayerError::from(e);
+                        events.emit(PlayerEvent::Error(error.code.code(), error.message.clone()));
+                        player_info.lock().unwrap().set_last_error(Some(error));
+                    }
+                }
+            }
+
+            // 到达循环结尾，跳回循环起点
+            if let Some(region) = loop_back.take() {
+                let Some(time_base) = time_base else {
+                    break;
+                };
+                let rate = decoder.codec_params().sample_rate.unwrap_or(time_base.denom);
+                let (start, _) = region.timestamps(time_base, rate);
+                let seek_to = SeekTo::TimeStamp { ts: start, track_id };
+                match reader.seek(SeekMode::Accurate, seek_to) {
+                    Ok(_) => {
+                        decoder.reset();
+                        after_loop = true;
+                    }
+                    Err(e) => return Err(e.into()),
                 }
-                last_update = now;
             }
 
             let packet = match reader.next_packet() {
@@ -216,6 +518,11 @@ impl StreamPlayer {
                 Err(Error::ResetRequired) => return Err(Error::ResetRequired.into()),
                 Err(Error::IoError(err)) => {
                     if err.kind() == std::io::ErrorKind::UnexpectedEof {
+                        // 循环到轨道末尾时从起点继续
+                        if active_loop.is_some() {
+                            loop_back = active_loop;
+                            continue;
+                        }
                         break;
                     }
                     return Err(Error::IoError(err).into());
@@ -229,68 +536,265 @@ impl StreamPlayer {
 
             match decoder.decode(&packet) {
                 Ok(decoded) => {
-                    if audio_output.is_none() {
-                        let spec = *decoded.spec();
-                        let duration = decoded.capacity() as u64;
-                        match create_audio_output(spec, duration) {
-                            Ok(output) => audio_output = Some(output),
-                            Err(e) => {
-                                return Err(
-                                    format!("Audio output initialization failed: {:?}", e).into()
-                                )
+                    if let Some(time_base) = time_base {
+                        let time = time_base.calc_time(packet.ts() + packet.dur());
+                        decoded_time = Some(time.seconds as f64 + time.frac);
+                    }
+
+                    // 按循环区间裁剪，保证跳转处前后采样紧密相接
+                    let trimmed;
+                    let decoded = match active_loop.zip(time_base) {
+                        Some((region, time_base)) => {
+                            let trim = region.packet_trim(
+                                time_base,
+                                decoded.spec().rate,
+                                packet.ts(),
+                                packet.dur(),
+                                after_loop,
+                            );
+                            if trim.loop_back {
+                                loop_back = Some(region);
+                            }
+                            if trim.start < decoded.frames() {
+                                after_loop = false;
+                            }
+                            if trim.start + trim.end >= decoded.frames() {
+                                continue;
                             }
+                            if trim.is_empty() {
+                                decoded
+                            } else {
+                                let mut buf = decoded.make_equivalent::<f32>();
+                                decoded.convert(&mut buf);
+                                buf.trim(trim.start, trim.end);
+                                trimmed = buf;
+                                trimmed.as_audio_buffer_ref()
+                            }
+                        }
+                        None => decoded,
+                    };
+
+                    // 应用回放增益
+                    let gained;
+                    let decoded = match replay_gain.as_mut() {
+                        Some(ramp) if ramp.is_ramping() || ramp.target() != 1.0 => {
+                            let mut buf = decoded.make_equivalent::<f32>();
+                            decoded.convert(&mut buf);
+                            apply_gain(&mut buf, ramp);
+                            gained = buf;
+                            gained.as_audio_buffer_ref()
                         }
+                        _ => decoded,
+                    };
+
+                    // 进入交叉淡化区间时开始混入下一首，循环播放时轨道不会结束
+                    if fading.is_none() && active_loop.is_none() {
+                        let remaining = time_base
+                            .zip(n_frames)
+                            .map(|(time_base, n_frames)| {
+                                time_base.calc_time(n_frames.saturating_sub(packet.ts()))
+                            });
+                        fading = Self::start_crossfade(
+                            remaining,
+                            *decoded.spec(),
+                            player_info,
+                            queue,
+                            preload,
+                        );
                     }
 
-                    if let Some(ref mut audio_output) = audio_output {
-                        // 获取当前音量并设置
-                        let current_volume = {
-                            let info = player_info.lock().unwrap();
-                            info.volume()
-                        };
-                        if let Err(e) = audio_output.set_volume(current_volume) {
-                            eprintln!("Volume setting failed: {:?}", e);
+                    if let Some(current) = fading.as_mut() {
+                        if current.fill(decoded.frames()) {
+                            let mixed = current.crossfader.mix(decoded);
+                            Self::write_output(audio_output, player_info, mixed.as_audio_buffer_ref())?;
+                            continue;
+                        }
+                        // 下一首规格不一致，放弃交叉淡化
+                        fading = None;
+                    }
+
+                    match fade_in.as_mut() {
+                        Some(crossfader)
+                            if !crossfader.is_finished() && crossfader.spec() == *decoded.spec() =>
+                        {
+                            let faded = crossfader.fade_in(decoded);
+                            Self::write_output(audio_output, player_info, faded.as_audio_buffer_ref())?;
+                        }
+                        _ => {
+                            fade_in = None;
+                            Self::write_output(audio_output, player_info, decoded)?;
                         }
-                        audio_output
-                            .write(decoded)
-                            .map_err(|e| format!("Audio write failed: {:?}", e))?;
                     }
                 }
+                Err(Error::IoError(_)) if active_loop.is_some() => loop_back = active_loop,
                 Err(Error::IoError(_)) => break,
                 Err(Error::DecodeError(_)) => continue,
                 Err(err) => return Err(err.into()),
             }
         }
 
-        // 清理音频输出
-        if let Some(mut audio_output) = audio_output {
-            audio_output.flush();
+        Ok(fading)
+    }
+
+    /// 从已解码的时间戳中扣除尚未播放的缓冲和设备延迟，得到实际听到的位置
+    fn update_position(
+        player_info: &PlayerInfoArc,
+        events: &EventDispatcher,
+        decoded_time: Option<f64>,
+        audio_output: &Option<AudioOutput>,
+    ) {
+        if let Some(decoded_time) = decoded_time {
+            let latency = audio_output.as_ref().map_or(0.0, AudioOutput::latency);
+            let position = ((decoded_time - latency).max(0.0) * 1000.0) as u64;
+            let mut info = player_info.lock().unwrap();
+            events.emit_position(info.current_time_ms(), position);
+            info.set_current_time_ms(position);
         }
+    }
 
-        Ok(0)
+    /// 设置播放状态，状态变化时发送事件
+    fn update_status(info: &mut PlayerInfo, events: &EventDispatcher, status: Status) {
+        if info.status() != status {
+            info.set_status(status.clone());
+            events.emit(PlayerEvent::StatusChanged(status));
+        }
+    }
+
+    /// 打开轨道，打开期间发送缓冲事件，已验证过的轨道复用响应头信息
+    fn open_track(
+        url: &str,
+        remote_info: Option<RemoteInfo>,
+        events: &EventDispatcher,
+    ) -> std::result::Result<PreparedTrack, PlayerError> {
+        events.emit(PlayerEvent::Buffering(true));
+        let result = match remote_info {
+            Some(remote_info) => PreparedTrack::open_with_info(url, remote_info),
+            None => PreparedTrack::open(url),
+        };
+        events.emit(PlayerEvent::Buffering(false));
+        result
+    }
+
+    /// 剩余时长进入交叉淡化区间且下一首已预加载完成时，取出下一首开始淡化
+    fn start_crossfade(
+        remaining: Option<Time>,
+        spec: SignalSpec,
+        player_info: &PlayerInfoArc,
+        queue: &PlayQueueArc,
+        preload: &mut Option<PreloadHandle>,
+    ) -> Option<FadingTrack> {
+        let (duration, curve, replay_gain_mode) = {
+            let info = player_info.lock().unwrap();
+            let (duration, curve) = info.crossfade();
+            (duration, curve, info.replay_gain_mode())
+        };
+        if duration == 0 || preload.is_none() {
+            return None;
+        }
+
+        let remaining = remaining?;
+        let remaining_ms = remaining.seconds * 1000 + (remaining.frac * 1000.0) as u64;
+        if remaining_ms > duration {
+            return None;
+        }
+
+        // 预加载尚未完成时不在解码循环中等待，下一个数据包再检查，直到结束仍未完成则无缝切换
+        if !preload.as_ref().is_some_and(|handle| handle.is_finished()) {
+            return None;
+        }
+        let track = preload.take()?.join().ok().flatten()?;
+        if queue.lock().unwrap().peek_next() != Some(track.url.as_str()) {
+            return None;
+        }
+
+        let total_frames = (remaining_ms * spec.rate as u64 / 1000) as usize;
+        let replay_gain = GainRamp::new(db_to_gain(track.replay_gain.gain_db(replay_gain_mode)));
+        Some(FadingTrack {
+            track,
+            crossfader: Crossfader::new(curve, spec, total_frames),
+            replay_gain,
+        })
+    }
+
+    /// 写入音频输出，音频规格变化或输出流失效时重建输出流
+    fn write_output(
+        audio_output: &mut Option<AudioOutput>,
+        player_info: &PlayerInfoArc,
+        decoded: AudioBufferRef<'_>,
+    ) -> std::result::Result<(), PlayerError> {
+        let spec = *decoded.spec();
+        let duration = decoded.capacity() as u64;
+
+        let mut recovering = false;
+        let mut track_start = false;
+        if let Some(mut old_output) =
+            audio_output.take_if(|output| !output.is_compatible(spec, duration))
+        {
+            recovering = old_output.is_failed();
+            old_output.flush();
+            track_start = old_output.has_pending_track_start();
+        }
+
+        if audio_output.is_none() {
+            let output = if recovering {
+                // 设备拔出或默认设备切换后从当前解码位置继续输出，失效时缓冲区中尚未播放的音频会丢失
+                recover_audio_output(spec, duration, Arc::clone(&player_info.output)).map_err(|e| {
+                    PlayerError::new(ErrorCode::AudioDeviceError, format!("Failed to recover audio output: {}", e))
+                })?
+            } else {
+                create_audio_output(spec, duration, Arc::clone(&player_info.output))?
+            };
+            *audio_output = Some(output);
+        }
+
+        // 旧输出流没有播放到的轨道起点在新输出流开头通知
+        if track_start && let Some(output) = audio_output.as_mut() {
+            output.mark_track_start();
+        }
+
+        if let Some(audio_output) = audio_output {
+            // 获取当前播放速度并设置，音量由输出控制直接生效
+            let (speed, speed_mode) = {
+                let info = player_info.lock().unwrap();
+                (info.speed(), info.speed_mode())
+            };
+            if let Err(e) = audio_output.set_speed(speed, speed_mode) {
+                eprintln!("Speed setting failed: {:?}", e);
+            }
+            audio_output.write(decoded)?;
+        }
+
+        Ok(())
     }
 
     /// 暂停
     pub fn pause(&mut self) -> std::result::Result<i32, Box<dyn std::error::Error>> {
         let mut info = self.player_info.lock().unwrap();
-        info.set_status(Status::Paused);
+        // 立即停止音频回调读取缓冲区，不必等待播放线程
+        self.player_info.output.set_paused(true);
+        Self::update_status(&mut info, &self.events, Status::Paused);
+        self.player_info.notify();
         Ok(0)
     }
 
     /// 恢复
     pub fn resume(&mut self) -> std::result::Result<i32, Box<dyn std::error::Error>> {
         let mut info = self.player_info.lock().unwrap();
-        info.set_status(Status::Playing);
+        self.player_info.output.set_paused(false);
+        Self::update_status(&mut info, &self.events, Status::Playing);
+        self.player_info.notify();
         Ok(0)
     }
 
     /// 停止
     pub fn stop(&mut self) -> std::result::Result<i32, Box<dyn std::error::Error>> {
-        // 设置停止状态
+        // 设置停止状态，输出在播放线程退出前淡出
         {
             let mut info = self.player_info.lock().unwrap();
-            info.set_status(Status::Stopped);
+            self.player_info.output.set_paused(true);
+            Self::update_status(&mut info, &self.events, Status::Stopped);
             info.set_current_time(0);
+            self.player_info.notify();
         }
 
         // 等待播放线程结束
@@ -298,6 +802,53 @@ impl StreamPlayer {
             let _ = handle.join();
         }
 
+        self.player_info.output.set_paused(false);
+        Ok(0)
+    }
+
+    /// 定位(毫秒)
+    pub fn seek(&mut self, position: u64) -> Result<i32, ErrorCode> {
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+
+        if matches!(info.status(), Status::Stopped | Status::Error) {
+            return Err(ErrorCode::PlayerOperationFailed);
+        }
+
+        if info.total_time_ms().is_some_and(|total_time| position > total_time) {
+            return Err(ErrorCode::InvalidParameter);
+        }
+
+        // 服务器不支持 Range 请求或轨道尚未打开时无法定位
+        if !info.seekable() {
+            return Err(ErrorCode::PlayerOperationFailed);
+        }
+
+        info.set_seek_position(Some(position));
+        // 暂停时唤醒播放线程处理定位
+        self.player_info.notify();
+        Ok(0)
+    }
+
+    /// 设置循环区间，传入 None 时取消循环
+    pub fn set_loop_region(&mut self, loop_region: Option<LoopRegion>) -> Result<i32, ErrorCode> {
+        if loop_region.is_some_and(|region| !region.is_valid()) {
+            return Err(ErrorCode::InvalidParameter);
+        }
+
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_loop_region(loop_region);
+        Ok(0)
+    }
+
+    /// A-B 重复(毫秒)
+    pub fn set_ab_repeat(&mut self, start: u64, end: u64) -> Result<i32, ErrorCode> {
+        self.set_loop_region(Some(LoopRegion::Millis { start, end }))
+    }
+
+    /// 整曲循环
+    pub fn set_repeat_track(&mut self, repeat_track: bool) -> Result<i32, ErrorCode> {
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_repeat_track(repeat_track);
         Ok(0)
     }
 
@@ -306,19 +857,512 @@ impl StreamPlayer {
         // 先停止播放
         self.stop()?;
 
-        // 重置播放器信息到初始状态
+        // 重置播放器信息到初始状态，保留音频后端、输出设备和备用设备选择
         {
             let mut info = self.player_info.lock().unwrap();
             info.reset();
+            info.set_output_host(self.player_info.output.output_host());
+            info.set_output_device(self.player_info.output.output_device());
+            info.set_fallback_device(self.player_info.output.fallback_device());
+            self.player_info.output.set_volume(info.output_gain(), 0);
+            self.player_info.output.set_fade_duration(DEFAULT_FADE_MS);
+            self.player_info.output.set_noise_shaping(false);
+        }
+
+        // 重置均衡器和响度归一化
+        {
+            let mut chain = self.player_info.output.chain();
+            chain.set_bypassed(self.equalizer_id, true);
+            chain.set_bypassed(self.loudness_id, true);
+            let stereo = self.player_info.lock().unwrap().stereo();
+            for (name, value) in stereo.parameters() {
+                chain.set_parameter(self.stereo_id, name, value);
+            }
+        }
+        self.player_info.loudness.set_target_lufs(DEFAULT_TARGET_LUFS);
+        self.equalizer.update(|settings| {
+            *settings = EqSettings::default();
+            true
+        });
+
+        // 清空播放队列
+        {
+            let mut queue = self.queue.lock().unwrap();
+            queue.clear();
         }
 
         Ok(0)
     }
 
+    /// 添加到播放队列
+    pub fn enqueue(&mut self, url: &str) -> Result<i32, ErrorCode> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(ErrorCode::UrlUnsupportedProtocol);
+        }
+
+        let mut queue = self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        Ok(queue.enqueue(url.to_string()) as i32)
+    }
+
+    /// 从播放队列移除
+    pub fn remove_from_queue(&mut self, index: usize) -> Result<i32, ErrorCode> {
+        let mut queue = self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        queue
+            .remove(index)
+            .map(|_| 0)
+            .ok_or(ErrorCode::QueueIndexOutOfBounds)
+    }
+
+    /// 调整播放队列顺序
+    pub fn move_in_queue(&mut self, from: usize, to: usize) -> Result<i32, ErrorCode> {
+        let mut queue = self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        if queue.move_entry(from, to) {
+            Ok(0)
+        } else {
+            Err(ErrorCode::QueueIndexOutOfBounds)
+        }
+    }
+
+    /// 清空播放队列
+    pub fn clear_queue(&mut self) -> Result<i32, ErrorCode> {
+        let mut queue = self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        queue.clear();
+        Ok(0)
+    }
+
+    /// 播放队列中的指定项
+    pub fn play_index(&mut self, index: usize) -> Result<i32, ErrorCode> {
+        self.play_queue_entry(|queue| queue.jump_to(index))
+    }
+
+    /// 下一首
+    pub fn play_next(&mut self) -> Result<i32, ErrorCode> {
+        self.play_queue_entry(PlayQueue::next_entry)
+    }
+
+    /// 上一首
+    pub fn play_previous(&mut self) -> Result<i32, ErrorCode> {
+        self.play_queue_entry(PlayQueue::previous_entry)
+    }
+
+    /// 移动队列当前项并开始播放，无法播放时恢复原来的当前项，避免下一首跳过一项
+    fn play_queue_entry<F>(&mut self, select: F) -> Result<i32, ErrorCode>
+    where
+        F: FnOnce(&mut PlayQueue) -> Option<String>,
+    {
+        let (url, previous) = {
+            let mut queue = self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+            let previous = queue.current_index();
+            (select(&mut queue).ok_or(ErrorCode::QueueIndexOutOfBounds)?, previous)
+        };
+
+        let result = self.start_playback(&url, true, PlayOptions::default());
+        if result.is_err() {
+            self.queue.lock().map_err(|_| ErrorCode::PlayerLockFailed)?.restore_current(previous);
+        }
+        result
+    }
+
+    /// 播放队列
+    pub fn get_queue(&self) -> PlayQueue {
+        let queue = self.queue.lock().unwrap();
+        queue.clone()
+    }
+
     /// 音量
     pub fn set_volume(&mut self, volume: f32) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        self.fade_volume(volume, VOLUME_RAMP_MS)
+    }
+
+    /// 在指定时长(毫秒)内渐变到目标音量
+    pub fn fade_volume(
+        &mut self,
+        volume: f32,
+        duration: u64,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
         let mut info = self.player_info.lock().unwrap();
         info.set_volume(volume)?;
+        self.player_info.output.set_volume(info.output_gain(), duration);
+        Ok(0)
+    }
+
+    /// 音量(dB)
+    pub fn set_volume_db(&mut self, volume_db: f32) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_volume_db(volume_db)?;
+        self.player_info.output.set_volume(info.output_gain(), VOLUME_RAMP_MS);
+        Ok(0)
+    }
+
+    /// 音量曲线
+    pub fn set_volume_curve(
+        &mut self,
+        volume_curve: VolumeCurve,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_volume_curve(volume_curve);
+        self.player_info.output.set_volume(info.output_gain(), VOLUME_RAMP_MS);
+        Ok(0)
+    }
+
+    /// 回放增益模式，正在播放的轨道随之渐变到新的增益
+    pub fn set_replay_gain_mode(
+        &mut self,
+        replay_gain_mode: ReplayGainMode,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_replay_gain_mode(replay_gain_mode);
+        Ok(0)
+    }
+
+    /// 静音，保留音量设置
+    pub fn set_muted(&mut self, muted: bool) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_muted(muted);
+        self.player_info.output.set_volume(info.output_gain(), VOLUME_RAMP_MS);
+        Ok(0)
+    }
+
+    /// 前级增益(dB)，高于 0dB 时由限制器防止削波
+    pub fn set_preamp(&mut self, preamp_db: f32) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_preamp_db(preamp_db)?;
+        self.player_info.output.set_volume(info.output_gain(), VOLUME_RAMP_MS);
+        Ok(0)
+    }
+
+    /// 暂停、恢复、开始和停止时的淡入淡出时长(毫秒)
+    pub fn set_fade_duration(
+        &mut self,
+        duration: u64,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_fade_duration(duration);
+        self.player_info.output.set_fade_duration(duration);
+        Ok(0)
+    }
+
+    /// 选择音频后端，None 表示默认后端，输出设备恢复为该后端的默认设备
+    pub fn set_output_host(&mut self, host: Option<String>) -> Result<i32, ErrorCode> {
+        // 统一为后端的标准名称
+        let host = match host {
+            Some(name) => Some(
+                find_host(Some(&name))
+                    .map_err(|_| ErrorCode::AudioHostUnavailable)?
+                    .id()
+                    .name()
+                    .to_string(),
+            ),
+            None => None,
+        };
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_output_host(host.clone());
+        info.set_output_device(None);
+        info.set_fallback_device(None);
+        self.player_info.output.set_output_host(host);
+        Ok(0)
+    }
+
+    /// 在当前音频后端中选择输出设备，None 表示默认设备，正在播放时切换到新设备
+    pub fn set_output_device(&mut self, name: Option<String>) -> Result<i32, ErrorCode> {
+        if name.is_some() {
+            let host = self.player_info.output.output_host();
+            find_output_device(host.as_deref(), name.as_deref()).map_err(|_| ErrorCode::AudioDeviceNotFound)?;
+        }
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_output_device(name.clone());
+        self.player_info.output.set_output_device(name);
+        Ok(0)
+    }
+
+    /// 选择的设备拔出或失效时使用的备用设备，None 表示直接使用默认设备
+    pub fn set_fallback_device(&mut self, name: Option<String>) -> Result<i32, ErrorCode> {
+        if name.is_some() {
+            let host = self.player_info.output.output_host();
+            find_output_device(host.as_deref(), name.as_deref()).map_err(|_| ErrorCode::AudioDeviceNotFound)?;
+        }
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_fallback_device(name.clone());
+        self.player_info.output.set_fallback_device(name);
+        Ok(0)
+    }
+
+    /// 恢复之前保存的后端、输出设备和备用设备名称
+    ///
+    /// 设备选择只保存在内存中，由调用方持久化设备名称并在启动时恢复；恢复时不检查设备当前是否可用，
+    /// 打开输出流时不可用的设备依次由备用设备和默认设备代替，设备重新插入后下一次打开输出流时再使用
+    pub fn restore_output_device(
+        &mut self,
+        host: Option<String>,
+        device: Option<String>,
+        fallback_device: Option<String>,
+    ) -> Result<i32, ErrorCode> {
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_output_host(host.clone());
+        info.set_output_device(device.clone());
+        info.set_fallback_device(fallback_device.clone());
+        let output = &self.player_info.output;
+        output.set_output_host(host);
+        output.set_output_device(device);
+        output.set_fallback_device(fallback_device);
+        Ok(0)
+    }
+
+    /// 按当前音频后端设备列表中的ID选择输出设备，保存的是设备名称
+    pub fn set_output_device_by_id(&mut self, id: usize) -> Result<i32, ErrorCode> {
+        let host = self.player_info.output.output_host();
+        let name = device_name_by_id(host.as_deref(), id).map_err(|_| ErrorCode::AudioDeviceNotFound)?;
+        self.set_output_device(Some(name))
+    }
+
+    /// 设备只支持 16 位整数格式时，量化是否使用噪声整形
+    pub fn set_noise_shaping(
+        &mut self,
+        noise_shaping: bool,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_noise_shaping(noise_shaping);
+        self.player_info.output.set_noise_shaping(noise_shaping);
+        Ok(0)
+    }
+
+    /// 播放速度
+    pub fn set_speed(&mut self, speed: f32) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_speed(speed)?;
+        Ok(0)
+    }
+
+    /// 变速模式
+    pub fn set_speed_mode(
+        &mut self,
+        speed_mode: SpeedMode,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_speed_mode(speed_mode);
+        Ok(0)
+    }
+
+    /// 交叉淡化
+    pub fn set_crossfade(
+        &mut self,
+        duration: u64,
+        curve: CrossfadeCurve,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        let mut info = self.player_info.lock().unwrap();
+        info.set_crossfade(duration, curve);
+        Ok(0)
+    }
+
+    /// 在处理链的指定位置插入音频处理器，返回处理器ID
+    pub fn add_processor(&mut self, index: usize, processor: Box<dyn AudioProcessor>) -> u64 {
+        self.player_info.output.chain().insert(index, processor)
+    }
+
+    /// 从处理链中移除音频处理器
+    pub fn remove_processor(&mut self, id: u64) -> Result<i32, ErrorCode> {
+        // 移除的处理器在淡出完成后由音频回调释放
+        if self.player_info.output.chain().remove(id) {
+            Ok(0)
+        } else {
+            Err(ErrorCode::AudioProcessorNotFound)
+        }
+    }
+
+    /// 旁路或恢复音频处理器
+    pub fn set_processor_bypassed(&mut self, id: u64, bypassed: bool) -> Result<i32, ErrorCode> {
+        if self.player_info.output.chain().set_bypassed(id, bypassed) {
+            Ok(0)
+        } else {
+            Err(ErrorCode::AudioProcessorNotFound)
+        }
+    }
+
+    /// 设置音频处理器参数，参数由音频回调应用，处理器不接受的参数被忽略
+    pub fn set_processor_parameter(&mut self, id: u64, name: &str, value: f32) -> Result<i32, ErrorCode> {
+        if self.player_info.output.chain().set_parameter(id, name, value) {
+            Ok(0)
+        } else {
+            Err(ErrorCode::AudioProcessorNotFound)
+        }
+    }
+
+    /// 处理链中的处理器ID和名称
+    pub fn get_processors(&self) -> Vec<(u64, String)> {
+        self.player_info.output.chain().processors()
+    }
+
+    /// 对没有回放增益的轨道进行响度归一化，目标响度单位为 LUFS
+    pub fn set_loudness_normalization(&mut self, enabled: bool, target_lufs: f32) -> Result<i32, ErrorCode> {
+        if !(MIN_TARGET_LUFS..=MAX_TARGET_LUFS).contains(&target_lufs) {
+            return Err(ErrorCode::InvalidParameter);
+        }
+        self.player_info.loudness.set_target_lufs(target_lufs);
+        self.set_processor_bypassed(self.loudness_id, !enabled)?;
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_loudness_normalization(enabled, target_lufs);
+        Ok(0)
+    }
+
+    /// 当前测得的积分响度(LUFS)和应用的归一化增益(dB)
+    pub fn get_loudness(&self) -> (Option<f32>, f32) {
+        let loudness = &self.player_info.loudness;
+        (loudness.loudness(), loudness.gain_db())
+    }
+
+    /// 左右平衡(-1.0 到 1.0)
+    pub fn set_balance(&mut self, balance: f32) -> Result<i32, ErrorCode> {
+        self.update_stereo("balance", balance)
+    }
+
+    /// 声像(-1.0 到 1.0)，按等功率声像律移动声音，与只衰减一侧的平衡不同
+    pub fn set_pan(&mut self, pan: f32) -> Result<i32, ErrorCode> {
+        self.update_stereo("pan", pan)
+    }
+
+    /// 声道增益(dB)，0 为左声道，1 为右声道
+    pub fn set_channel_gain(&mut self, channel: usize, gain_db: f32) -> Result<i32, ErrorCode> {
+        match channel {
+            0 => self.update_stereo("left_gain", gain_db),
+            1 => self.update_stereo("right_gain", gain_db),
+            _ => Err(ErrorCode::InvalidParameter),
+        }
+    }
+
+    /// 混合为单声道，多声道输出时只混合前左和前右声道
+    pub fn set_mono(&mut self, mono: bool) -> Result<i32, ErrorCode> {
+        self.update_stereo("mono", mono as i32 as f32)
+    }
+
+    /// 互换左右声道
+    pub fn set_swap_channels(&mut self, swap: bool) -> Result<i32, ErrorCode> {
+        self.update_stereo("swap", swap as i32 as f32)
+    }
+
+    fn update_stereo(&mut self, name: &str, value: f32) -> Result<i32, ErrorCode> {
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        if !info.stereo.set_parameter(name, value) {
+            return Err(ErrorCode::InvalidParameter);
+        }
+        self.player_info.output.chain().set_parameter(self.stereo_id, name, value);
+        Ok(0)
+    }
+
+    /// 启用或旁路均衡器
+    pub fn set_equalizer_enabled(&mut self, enabled: bool) -> Result<i32, ErrorCode> {
+        self.set_processor_bypassed(self.equalizer_id, !enabled)?;
+        let mut info = self.player_info.lock().map_err(|_| ErrorCode::PlayerLockFailed)?;
+        info.set_equalizer_enabled(enabled);
+        Ok(0)
+    }
+
+    /// 均衡模式
+    pub fn set_equalizer_mode(&mut self, mode: EqMode) -> Result<i32, ErrorCode> {
+        self.update_equalizer(|settings| {
+            settings.set_mode(mode);
+            true
+        })
+    }
+
+    /// 均衡频段增益(dB)
+    pub fn set_equalizer_band_gain(&mut self, index: usize, gain_db: f32) -> Result<i32, ErrorCode> {
+        self.update_equalizer(|settings| settings.set_band_gain(index, gain_db))
+    }
+
+    /// 参数均衡频段，索引等于频段数时追加
+    pub fn set_equalizer_band(&mut self, index: usize, band: EqBand) -> Result<i32, ErrorCode> {
+        self.update_equalizer(|settings| settings.set_band(index, band))
+    }
+
+    /// 移除参数均衡频段
+    pub fn remove_equalizer_band(&mut self, index: usize) -> Result<i32, ErrorCode> {
+        self.update_equalizer(|settings| settings.remove_band(index))
+    }
+
+    /// 均衡器前级增益(dB)
+    pub fn set_equalizer_preamp(&mut self, preamp_db: f32) -> Result<i32, ErrorCode> {
+        self.update_equalizer(|settings| settings.set_preamp(preamp_db))
+    }
+
+    /// 均衡器设置
+    pub fn get_equalizer(&self) -> EqSettings {
+        self.equalizer.settings()
+    }
+
+    /// 加载均衡器预设，优先使用保存的预设，其次是内置预设
+    pub fn load_equalizer_preset(&mut self, name: &str) -> Result<i32, ErrorCode> {
+        let preset = self
+            .equalizer_presets
+            .get(name)
+            .cloned()
+            .or_else(|| builtin_preset(name))
+            .ok_or(ErrorCode::EqualizerPresetNotFound)?;
+        self.update_equalizer(|settings| {
+            *settings = preset.settings;
+            true
+        })
+    }
+
+    /// 导入 JSON 格式的均衡器预设，保存后立即加载
+    pub fn import_equalizer_preset(&mut self, json: &str) -> Result<i32, ErrorCode> {
+        let preset = EqPreset::from_json(json).ok_or(ErrorCode::InvalidParameter)?;
+        self.equalizer_presets.insert(preset.name.clone(), preset.clone());
+        self.update_equalizer(|settings| {
+            *settings = preset.settings;
+            true
+        })
+    }
+
+    /// 将当前均衡器设置保存为预设，返回 JSON
+    pub fn export_equalizer_preset(&mut self, name: &str) -> Result<String, ErrorCode> {
+        if name.is_empty() {
+            return Err(ErrorCode::InvalidParameter);
+        }
+        let preset = EqPreset {
+            name: name.to_string(),
+            settings: self.equalizer.settings(),
+        };
+        let json = preset.to_json();
+        self.equalizer_presets.insert(preset.name.clone(), preset);
+        Ok(json)
+    }
+
+    /// 内置和保存的均衡器预设名称
+    pub fn get_equalizer_presets(&self) -> Vec<String> {
+        let mut names: Vec<String> = builtin_presets().into_iter().map(|preset| preset.name).collect();
+        let mut saved: Vec<String> = self
+            .equalizer_presets
+            .keys()
+            .filter(|name| !names.contains(name))
+            .cloned()
+            .collect();
+        saved.sort();
+        names.extend(saved);
+        names
+    }
+
+    fn update_equalizer<F>(&mut self, f: F) -> Result<i32, ErrorCode>
+    where
+        F: FnOnce(&mut EqSettings) -> bool,
+    {
+        if self.equalizer.update(f) {
+            Ok(0)
+        } else {
+            Err(ErrorCode::InvalidParameter)
+        }
+    }
+
+    /// 设置事件监听器
+    pub fn set_listener(&mut self, listener: Option<Box<dyn PlayerListener>>) {
+        self.events.set_listener(listener);
+    }
+
+    /// 设置播放位置事件间隔(毫秒)
+    pub fn set_position_interval(
+        &mut self,
+        interval: u64,
+    ) -> std::result::Result<i32, Box<dyn std::error::Error>> {
+        self.events.set_position_interval(interval);
         Ok(0)
     }
 
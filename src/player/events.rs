@@ -0,0 +1,98 @@
+use crate::player::Status;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+/// 播放器事件
+#[derive(Debug, Clone)]
+pub enum PlayerEvent {
+    /// 播放状态变化
+    StatusChanged(Status),
+    /// 播放位置变化(毫秒)
+    PositionChanged(u64),
+    /// 当前轨道播放结束
+    TrackEnded,
+    /// 开始或结束缓冲
+    Buffering(bool),
+    /// 播放错误(错误码, 详细信息)
+    Error(i32, String),
+}
+
+/// 播放器事件监听器
+pub trait PlayerListener: Send {
+    /// 处理事件，在事件分发线程中调用
+    fn on_event(&mut self, event: PlayerEvent);
+}
+
+/// 发送到分发线程的消息
+enum DispatchMessage {
+    Event(PlayerEvent),
+    SetListener(Option<Box<dyn PlayerListener>>),
+}
+
+/// 事件分发器
+///
+/// 事件和监听器的替换都通过通道发送到独立的分发线程，监听器只在分发线程中访问，
+/// 调用监听器时不持有任何锁，监听器中可以安全地回调播放器
+#[derive(Clone)]
+pub struct EventDispatcher {
+    sender: Sender<DispatchMessage>,
+    /// 播放位置事件间隔(毫秒)，0 表示关闭
+    position_interval: Arc<AtomicU64>,
+}
+
+impl EventDispatcher {
+    /// 创建事件分发器并启动分发线程
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel::<DispatchMessage>();
+
+        thread::spawn(move || {
+            let mut listener: Option<Box<dyn PlayerListener>> = None;
+            for message in receiver {
+                match message {
+                    DispatchMessage::Event(event) => {
+                        if let Some(listener) = listener.as_mut() {
+                            listener.on_event(event);
+                        }
+                    }
+                    DispatchMessage::SetListener(new_listener) => listener = new_listener,
+                }
+            }
+        });
+
+        Self {
+            sender,
+            position_interval: Arc::new(AtomicU64::new(1000)),
+        }
+    }
+
+    /// 设置监听器，传入 None 时移除，在此之前发送的事件仍由旧监听器处理
+    pub fn set_listener(&self, listener: Option<Box<dyn PlayerListener>>) {
+        let _ = self.sender.send(DispatchMessage::SetListener(listener));
+    }
+
+    /// 设置播放位置事件间隔(毫秒)
+    pub fn set_position_interval(&self, interval: u64) {
+        self.position_interval.store(interval, Ordering::Relaxed);
+    }
+
+    /// 发送事件
+    pub fn emit(&self, event: PlayerEvent) {
+        let _ = self.sender.send(DispatchMessage::Event(event));
+    }
+
+    /// 播放位置跨过事件间隔时发送位置事件
+    pub fn emit_position(&self, old_position: u64, new_position: u64) {
+        let interval = self.position_interval.load(Ordering::Relaxed);
+        if interval > 0 && old_position / interval != new_position / interval {
+            self.emit(PlayerEvent::PositionChanged(new_position));
+        }
+    }
+}
+
+impl Default for EventDispatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,3 +1,12 @@
+use crate::audio::crossfade::CrossfadeCurve;
+use crate::audio::gain::{DEFAULT_FADE_MS, MAX_PREAMP_DB, VolumeCurve, db_to_gain, gain_to_db};
+use crate::audio::loudness::DEFAULT_TARGET_LUFS;
+use crate::audio::replaygain::ReplayGainMode;
+use crate::audio::stereo::StereoSettings;
+use crate::audio::stretch::{MAX_SPEED, MIN_SPEED, SpeedMode};
+use crate::error_codes::PlayerError;
+use crate::player::LoopRegion;
+
 /// 播放状态
 #[derive(Debug, Clone, PartialEq)]
 pub enum Status {
@@ -9,6 +18,8 @@ pub enum Status {
     Paused,
     /// 加载中
     Loading,
+    /// 错误
+    Error,
 }
 
 /// 播放器信息
@@ -20,8 +31,60 @@ pub struct PlayerInfo {
     pub current_time: u64,
     /// 总时长(秒)
     pub total_time: Option<u64>,
-    /// 音量
+    /// 当前时间(毫秒)
+    pub current_time_ms: u64,
+    /// 总时长(毫秒)
+    pub total_time_ms: Option<u64>,
+    /// 音量位置(0.0-1.0)，按音量曲线换算为增益
     pub volume: f32,
+    /// 音量曲线
+    pub volume_curve: VolumeCurve,
+    /// 是否静音，静音时保留音量设置
+    pub muted: bool,
+    /// 前级增益(dB)
+    pub preamp_db: f32,
+    /// 回放增益模式
+    pub replay_gain_mode: ReplayGainMode,
+    /// 当前轨道应用的回放增益(dB)
+    pub replay_gain_db: f32,
+    /// 是否对没有回放增益的轨道进行响度归一化
+    pub loudness_normalization: bool,
+    /// 响度归一化的目标响度(LUFS)
+    pub target_loudness: f32,
+    /// 当前轨道是否支持定位
+    pub seekable: bool,
+    /// 待处理的定位请求(毫秒)
+    pub seek_position: Option<u64>,
+    /// 交叉淡化时长(毫秒)，0 表示关闭
+    pub crossfade_duration: u64,
+    /// 交叉淡化曲线
+    pub crossfade_curve: CrossfadeCurve,
+    /// 暂停、恢复、开始和停止时的淡入淡出时长(毫秒)
+    pub fade_duration: u64,
+    /// 输出 16 位整数时是否启用噪声整形
+    pub noise_shaping: bool,
+    /// 选择的音频后端名称，未选择时使用默认后端
+    pub output_host: Option<String>,
+    /// 选择的输出设备名称，未选择时使用默认设备
+    pub output_device: Option<String>,
+    /// 选择的设备不可用时使用的备用设备名称
+    pub fallback_device: Option<String>,
+    /// 是否启用均衡器
+    pub equalizer_enabled: bool,
+    /// 左右平衡、声道增益、单声道混音和声道互换
+    pub stereo: StereoSettings,
+    /// 播放速度
+    pub speed: f32,
+    /// 变速模式
+    pub speed_mode: SpeedMode,
+    /// 循环区间
+    pub loop_region: Option<LoopRegion>,
+    /// 是否使用 LOOPSTART/LOOPLENGTH 标签
+    pub use_loop_tags: bool,
+    /// 整曲循环
+    pub repeat_track: bool,
+    /// 最近一次播放错误
+    pub last_error: Option<PlayerError>,
 }
 
 impl PlayerInfo {
@@ -31,7 +94,33 @@ impl PlayerInfo {
             status: Status::Stopped,
             current_time: 0,
             total_time: None,
+            current_time_ms: 0,
+            total_time_ms: None,
             volume: 1.0,
+            volume_curve: VolumeCurve::Linear,
+            muted: false,
+            preamp_db: 0.0,
+            replay_gain_mode: ReplayGainMode::Off,
+            replay_gain_db: 0.0,
+            loudness_normalization: false,
+            target_loudness: DEFAULT_TARGET_LUFS,
+            seekable: false,
+            seek_position: None,
+            crossfade_duration: 0,
+            crossfade_curve: CrossfadeCurve::EqualPower,
+            fade_duration: DEFAULT_FADE_MS,
+            noise_shaping: false,
+            output_host: None,
+            output_device: None,
+            fallback_device: None,
+            equalizer_enabled: false,
+            stereo: StereoSettings::default(),
+            speed: 1.0,
+            speed_mode: SpeedMode::Resample,
+            loop_region: None,
+            use_loop_tags: false,
+            repeat_track: false,
+            last_error: None,
         }
     }
 
@@ -51,11 +140,116 @@ impl PlayerInfo {
         self.current_time
     }
 
+    /// 总时长(毫秒)
+    pub fn total_time_ms(&self) -> Option<u64> {
+        self.total_time_ms
+    }
+
+    /// 当前轨道是否支持定位
+    pub fn seekable(&self) -> bool {
+        self.seekable
+    }
+
+    /// 当前时间(毫秒)
+    pub fn current_time_ms(&self) -> u64 {
+        self.current_time_ms
+    }
+
     /// 音量
     pub fn volume(&self) -> f32 {
         self.volume
     }
 
+    /// 音量曲线
+    pub fn volume_curve(&self) -> VolumeCurve {
+        self.volume_curve
+    }
+
+    /// 音量(dB)，不含静音和前级增益
+    pub fn volume_db(&self) -> f32 {
+        gain_to_db(self.volume_curve.gain(self.volume))
+    }
+
+    /// 是否静音
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// 前级增益(dB)
+    pub fn preamp_db(&self) -> f32 {
+        self.preamp_db
+    }
+
+    /// 实际输出增益，包含音量曲线、静音和前级增益
+    pub fn output_gain(&self) -> f32 {
+        if self.muted {
+            return 0.0;
+        }
+        self.volume_curve.gain(self.volume) * db_to_gain(self.preamp_db)
+    }
+
+    /// 交叉淡化设置
+    pub fn crossfade(&self) -> (u64, CrossfadeCurve) {
+        (self.crossfade_duration, self.crossfade_curve)
+    }
+
+    /// 淡入淡出时长(毫秒)
+    pub fn fade_duration(&self) -> u64 {
+        self.fade_duration
+    }
+
+    /// 回放增益模式
+    pub fn replay_gain_mode(&self) -> ReplayGainMode {
+        self.replay_gain_mode
+    }
+
+    /// 当前轨道应用的回放增益(dB)
+    pub fn replay_gain_db(&self) -> f32 {
+        self.replay_gain_db
+    }
+
+    /// 响度归一化设置，返回是否启用和目标响度(LUFS)
+    pub fn loudness_normalization(&self) -> (bool, f32) {
+        (self.loudness_normalization, self.target_loudness)
+    }
+
+    /// 立体声设置
+    pub fn stereo(&self) -> StereoSettings {
+        self.stereo
+    }
+
+    /// 是否启用均衡器
+    pub fn is_equalizer_enabled(&self) -> bool {
+        self.equalizer_enabled
+    }
+
+    /// 播放速度
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// 变速模式
+    pub fn speed_mode(&self) -> SpeedMode {
+        self.speed_mode
+    }
+
+    /// 当前轨道生效的循环区间，优先使用手动设置的区间，其次是循环标签，最后是整曲循环
+    pub fn active_loop(&self, loop_tags: Option<LoopRegion>) -> Option<LoopRegion> {
+        self.loop_region
+            .or(loop_tags.filter(|_| self.use_loop_tags))
+            .or(self.repeat_track.then_some(LoopRegion::WHOLE_TRACK))
+    }
+
+    /// 最近一次播放错误
+    pub fn last_error(&self) -> Option<&PlayerError> {
+        self.last_error.as_ref()
+    }
+
+    /// 取出待处理的定位请求
+    pub fn take_seek_position(&mut self) -> Option<u64> {
+        self.seek_position.take()
+    }
+
     // 数据设置方法
     /// 播放状态
     pub fn set_status(&mut self, status: Status) {
@@ -65,11 +259,92 @@ impl PlayerInfo {
     /// 总时长
     pub fn set_total_time(&mut self, total_time: Option<u64>) {
         self.total_time = total_time;
+        self.total_time_ms = total_time.map(|total_time| total_time * 1000);
     }
 
     /// 当前时间
     pub fn set_current_time(&mut self, current_time: u64) {
         self.current_time = current_time;
+        self.current_time_ms = current_time * 1000;
+    }
+
+    /// 总时长(毫秒)
+    pub fn set_total_time_ms(&mut self, total_time_ms: Option<u64>) {
+        self.total_time_ms = total_time_ms;
+        self.total_time = total_time_ms.map(|total_time_ms| total_time_ms / 1000);
+    }
+
+    /// 当前轨道是否支持定位
+    pub fn set_seekable(&mut self, seekable: bool) {
+        self.seekable = seekable;
+    }
+
+    /// 当前时间(毫秒)
+    pub fn set_current_time_ms(&mut self, current_time_ms: u64) {
+        self.current_time_ms = current_time_ms;
+        self.current_time = current_time_ms / 1000;
+    }
+
+    /// 最近一次播放错误
+    pub fn set_last_error(&mut self, last_error: Option<PlayerError>) {
+        self.last_error = last_error;
+    }
+
+    /// 定位请求
+    pub fn set_seek_position(&mut self, seek_position: Option<u64>) {
+        self.seek_position = seek_position;
+    }
+
+    /// 交叉淡化设置
+    pub fn set_crossfade(&mut self, duration: u64, curve: CrossfadeCurve) {
+        self.crossfade_duration = duration;
+        self.crossfade_curve = curve;
+    }
+
+    /// 淡入淡出时长(毫秒)
+    pub fn set_fade_duration(&mut self, fade_duration: u64) {
+        self.fade_duration = fade_duration;
+    }
+
+    /// 选择的音频后端名称
+    pub fn set_output_host(&mut self, output_host: Option<String>) {
+        self.output_host = output_host;
+    }
+
+    /// 选择的输出设备名称
+    pub fn set_output_device(&mut self, output_device: Option<String>) {
+        self.output_device = output_device;
+    }
+
+    /// 备用设备名称
+    pub fn set_fallback_device(&mut self, fallback_device: Option<String>) {
+        self.fallback_device = fallback_device;
+    }
+
+    /// 输出 16 位整数时是否启用噪声整形
+    pub fn set_noise_shaping(&mut self, noise_shaping: bool) {
+        self.noise_shaping = noise_shaping;
+    }
+
+    /// 回放增益模式
+    pub fn set_replay_gain_mode(&mut self, replay_gain_mode: ReplayGainMode) {
+        self.replay_gain_mode = replay_gain_mode;
+    }
+
+    /// 当前轨道应用的回放增益(dB)
+    pub fn set_replay_gain_db(&mut self, replay_gain_db: f32) {
+        self.replay_gain_db = replay_gain_db;
+    }
+
+    /// 响度归一化设置
+    pub fn set_loudness_normalization(&mut self, enabled: bool, target_loudness: f32) {
+        self.loudness_normalization = enabled;
+        self.target_loudness = target_loudness;
+    }
+
+    /// 是否启用均衡器
+    pub fn set_equalizer_enabled(&mut self, equalizer_enabled: bool) {
+        self.equalizer_enabled = equalizer_enabled;
     }
 
     /// 音量
@@ -81,6 +356,63 @@ impl PlayerInfo {
         Ok(())
     }
 
+    /// 音量(dB)，按当前音量曲线换算为音量位置
+    pub fn set_volume_db(&mut self, volume_db: f32) -> Result<(), String> {
+        if volume_db.is_nan() || volume_db > 0.0 {
+            return Err("Volume must not be above 0 dB".to_string());
+        }
+        self.volume = self.volume_curve.position(db_to_gain(volume_db));
+        Ok(())
+    }
+
+    /// 音量曲线
+    pub fn set_volume_curve(&mut self, volume_curve: VolumeCurve) {
+        self.volume_curve = volume_curve;
+    }
+
+    /// 静音
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    /// 前级增益(dB)
+    pub fn set_preamp_db(&mut self, preamp_db: f32) -> Result<(), String> {
+        if !(0.0..=MAX_PREAMP_DB).contains(&preamp_db) {
+            return Err("Preamp must be between 0 and 12 dB".to_string());
+        }
+        self.preamp_db = preamp_db;
+        Ok(())
+    }
+
+    /// 播放速度
+    pub fn set_speed(&mut self, speed: f32) -> Result<(), String> {
+        if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+            return Err("Speed must be between 0.5 and 2.0".to_string());
+        }
+        self.speed = speed;
+        Ok(())
+    }
+
+    /// 变速模式
+    pub fn set_speed_mode(&mut self, speed_mode: SpeedMode) {
+        self.speed_mode = speed_mode;
+    }
+
+    /// 循环区间
+    pub fn set_loop_region(&mut self, loop_region: Option<LoopRegion>) {
+        self.loop_region = loop_region;
+    }
+
+    /// 是否使用循环标签
+    pub fn set_use_loop_tags(&mut self, use_loop_tags: bool) {
+        self.use_loop_tags = use_loop_tags;
+    }
+
+    /// 整曲循环
+    pub fn set_repeat_track(&mut self, repeat_track: bool) {
+        self.repeat_track = repeat_track;
+    }
+
     /// 重置播放器信息到初始状态
     pub fn reset(&mut self) {
         *self = Self::new();
@@ -0,0 +1,219 @@
+use symphonia::core::meta::Tag;
+use symphonia::core::units::TimeBase;
+
+/// 循环区间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoopRegion {
+    /// 按采样位置(帧)指定，结束位置为空时循环到轨道末尾
+    Samples { start: u64, end: Option<u64> },
+    /// A-B 重复(毫秒)
+    Millis { start: u64, end: u64 },
+}
+
+impl LoopRegion {
+    /// 整曲循环
+    pub const WHOLE_TRACK: LoopRegion = LoopRegion::Samples { start: 0, end: None };
+
+    /// 区间是否有效
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            LoopRegion::Samples { start, end } => end.is_none_or(|end| end > start),
+            LoopRegion::Millis { start, end } => end > start,
+        }
+    }
+
+    /// 换算为时间基下的时间戳区间
+    pub fn timestamps(&self, time_base: TimeBase, sample_rate: u32) -> (u64, Option<u64>) {
+        match *self {
+            LoopRegion::Samples { start, end } => (
+                frames_to_ts(start, time_base, sample_rate),
+                end.map(|end| frames_to_ts(end, time_base, sample_rate)),
+            ),
+            LoopRegion::Millis { start, end } => {
+                (ms_to_ts(start, time_base), Some(ms_to_ts(end, time_base)))
+            }
+        }
+    }
+
+    /// 从 LOOPSTART/LOOPLENGTH 标签读取循环区间
+    pub fn from_tags(tags: &[Tag]) -> Option<Self> {
+        let find = |name: &str| {
+            tags.iter()
+                .find(|tag| tag.key.to_ascii_uppercase().ends_with(name))
+                .and_then(|tag| tag.value.to_string().trim().parse::<u64>().ok())
+        };
+
+        let start = find("LOOPSTART")?;
+        let end = find("LOOPLENGTH")
+            .filter(|length| *length > 0)
+            .map(|length| start + length);
+        Some(LoopRegion::Samples { start, end })
+    }
+
+    /// 计算数据包在循环区间中需要裁剪的帧数，`after_loop` 表示刚从结尾跳回起点
+    pub fn packet_trim(
+        &self,
+        time_base: TimeBase,
+        sample_rate: u32,
+        ts: u64,
+        dur: u64,
+        after_loop: bool,
+    ) -> PacketTrim {
+        let (start, end) = self.timestamps(time_base, sample_rate);
+        let packet_end = ts + dur;
+
+        // 定位只能落在数据包边界上，起点之前的帧需要丢弃
+        let head = if after_loop { start.saturating_sub(ts).min(dur) } else { 0 };
+        let (tail, loop_back) = match end {
+            Some(end) if packet_end >= end => (packet_end - end.max(ts), true),
+            _ => (0, false),
+        };
+
+        PacketTrim {
+            start: ts_to_frames(head, time_base, sample_rate) as usize,
+            end: ts_to_frames(tail, time_base, sample_rate) as usize,
+            loop_back,
+        }
+    }
+}
+
+/// 数据包裁剪
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacketTrim {
+    /// 开头丢弃的帧数
+    pub start: usize,
+    /// 结尾丢弃的帧数
+    pub end: usize,
+    /// 是否已到达循环结尾
+    pub loop_back: bool,
+}
+
+impl PacketTrim {
+    /// 是否需要裁剪
+    pub fn is_empty(&self) -> bool {
+        self.start == 0 && self.end == 0
+    }
+}
+
+/// 播放选项
+#[derive(Debug, Clone, Default)]
+pub struct PlayOptions {
+    /// 循环区间
+    pub loop_region: Option<LoopRegion>,
+    /// 是否读取 LOOPSTART/LOOPLENGTH 标签作为循环区间
+    pub use_loop_tags: bool,
+}
+
+/// 采样帧数换算为时间戳
+pub fn frames_to_ts(frames: u64, time_base: TimeBase, sample_rate: u32) -> u64 {
+    frames * time_base.denom as u64 / (time_base.numer as u64 * sample_rate as u64)
+}
+
+/// 时间戳换算为采样帧数
+pub fn ts_to_frames(ts: u64, time_base: TimeBase, sample_rate: u32) -> u64 {
+    ts * time_base.numer as u64 * sample_rate as u64 / time_base.denom as u64
+}
+
+fn ms_to_ts(ms: u64, time_base: TimeBase) -> u64 {
+    ms * time_base.denom as u64 / (time_base.numer as u64 * 1000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::meta::Value;
+
+    const RATE: u32 = 44100;
+    const PACKET: u64 = 1152;
+
+    fn time_base() -> TimeBase {
+        TimeBase::new(1, RATE)
+    }
+
+    fn trim(region: LoopRegion, ts: u64, after_loop: bool) -> PacketTrim {
+        region.packet_trim(time_base(), RATE, ts, PACKET, after_loop)
+    }
+
+    fn tag(key: &str, value: &str) -> Tag {
+        Tag::new(None, key, Value::String(value.to_string()))
+    }
+
+    #[test]
+    fn packet_ending_at_loop_end_is_not_trimmed() {
+        let region = LoopRegion::Samples { start: 0, end: Some(PACKET * 4) };
+        let trim = trim(region, PACKET * 3, false);
+        assert!(trim.is_empty());
+        assert!(trim.loop_back);
+    }
+
+    #[test]
+    fn packet_crossing_loop_end_drops_tail() {
+        let region = LoopRegion::Samples { start: 0, end: Some(PACKET * 3 + 100) };
+        assert!(!trim(region, PACKET * 2, false).loop_back);
+
+        let trim = trim(region, PACKET * 3, false);
+        assert_eq!(trim, PacketTrim { start: 0, end: PACKET as usize - 100, loop_back: true });
+    }
+
+    #[test]
+    fn packet_past_loop_end_is_dropped_entirely() {
+        let region = LoopRegion::Samples { start: 0, end: Some(PACKET * 2) };
+        let trim = trim(region, PACKET * 3, false);
+        assert_eq!(trim.end, PACKET as usize);
+        assert!(trim.loop_back);
+    }
+
+    #[test]
+    fn packet_starting_at_loop_start_is_not_trimmed() {
+        let region = LoopRegion::Samples { start: PACKET * 2, end: None };
+        assert!(trim(region, PACKET * 2, true).is_empty());
+    }
+
+    #[test]
+    fn packet_containing_loop_start_drops_head_after_loop() {
+        let region = LoopRegion::Samples { start: PACKET * 2 + 300, end: None };
+        assert_eq!(trim(region, PACKET * 2, true).start, 300);
+        assert!(trim(region, PACKET * 2, false).is_empty());
+        // 定位落在更早的数据包上时整包丢弃
+        assert_eq!(trim(region, PACKET, true).start, PACKET as usize);
+    }
+
+    #[test]
+    fn millis_region_converts_to_timestamps() {
+        let region = LoopRegion::Millis { start: 1000, end: 2500 };
+        assert_eq!(region.timestamps(time_base(), RATE), (44100, Some(110250)));
+
+        let coarse = TimeBase::new(1, 1000);
+        assert_eq!(region.timestamps(coarse, RATE), (1000, Some(2500)));
+    }
+
+    #[test]
+    fn samples_region_converts_with_coarse_time_base() {
+        let region = LoopRegion::Samples { start: 44100, end: Some(88200) };
+        assert_eq!(region.timestamps(TimeBase::new(1, 1000), RATE), (1000, Some(2000)));
+    }
+
+    #[test]
+    fn invalid_regions_are_rejected() {
+        assert!(LoopRegion::WHOLE_TRACK.is_valid());
+        assert!(!LoopRegion::Samples { start: 10, end: Some(10) }.is_valid());
+        assert!(!LoopRegion::Millis { start: 2000, end: 1000 }.is_valid());
+    }
+
+    #[test]
+    fn region_from_loop_tags() {
+        let tags = [tag("LOOPSTART", "1000"), tag("TXXX:LoopLength", " 5000 ")];
+        assert_eq!(
+            LoopRegion::from_tags(&tags),
+            Some(LoopRegion::Samples { start: 1000, end: Some(6000) })
+        );
+
+        let tags = [tag("LOOPSTART", "1000"), tag("LOOPLENGTH", "0")];
+        assert_eq!(
+            LoopRegion::from_tags(&tags),
+            Some(LoopRegion::Samples { start: 1000, end: None })
+        );
+
+        assert_eq!(LoopRegion::from_tags(&[tag("LOOPLENGTH", "5000")]), None);
+    }
+}
@@ -3,10 +3,18 @@
 //! 包含播放器核心功能、状态管理和数据
 
 pub mod core;
+pub mod events;
 pub mod info;
+pub mod looping;
 pub mod network;
+pub mod queue;
+pub mod track;
 
 // 重新导出常用类型
 pub use core::StreamPlayer;
+pub use events::{EventDispatcher, PlayerEvent, PlayerListener};
 pub use info::{PlayerInfo, Status};
-pub use network::NetworkMediaSource;
+pub use looping::{LoopRegion, PacketTrim, PlayOptions};
+pub use network::{NetworkMediaSource, RemoteInfo};
+pub use queue::PlayQueue;
+pub use track::PreparedTrack;
@@ -1,21 +1,97 @@
+use crate::error_codes::{ErrorCode, PlayerError};
+use std::time::Duration;
 use symphonia::core::io::MediaSource;
 
+/// 探测响应头的超时时间
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 网络资源的响应头信息
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RemoteInfo {
+    /// 文件大小(字节)
+    pub content_length: Option<u64>,
+    /// 服务器是否支持 Range 请求
+    pub accept_ranges: bool,
+}
+
+impl RemoteInfo {
+    /// 通过 HEAD 请求获取响应头
+    pub fn probe(url: &str) -> Result<Self, ErrorCode> {
+        let response = match ureq::head(url).timeout(PROBE_TIMEOUT).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Err(ErrorCode::MediaNotFound),
+            Err(ureq::Error::Status(_, _)) => return Err(ErrorCode::HttpError),
+            Err(ureq::Error::Transport(_)) => return Err(ErrorCode::ConnectionTimeout),
+        };
+
+        if !(200..300).contains(&response.status()) {
+            return Err(ErrorCode::HttpError);
+        }
+
+        Ok(Self {
+            content_length: response
+                .header("Content-Length")
+                .and_then(|len| len.parse::<u64>().ok()),
+            accept_ranges: response
+                .header("Accept-Ranges")
+                .is_some_and(|ranges| ranges.eq_ignore_ascii_case("bytes")),
+        })
+    }
+}
+
 /// 网络媒体源
 pub struct NetworkMediaSource {
     url: String,
     reader: Option<Box<dyn std::io::Read + Send + Sync>>,
+    /// 当前读取位置(字节)
+    position: u64,
+    /// 文件大小(字节)
+    content_length: Option<u64>,
+    /// 服务器是否支持 Range 请求
+    accept_ranges: bool,
 }
 
 impl NetworkMediaSource {
-    /// 创建网络媒体源
-    pub fn new(url: String) -> std::result::Result<Self, Box<dyn std::error::Error>> {
-        Ok(Self { url, reader: None })
+    /// 创建网络媒体源，探测失败时按不支持定位处理
+    pub fn new(url: String) -> std::result::Result<Self, PlayerError> {
+        let info = RemoteInfo::probe(&url).unwrap_or_default();
+        Ok(Self::with_info(url, info))
+    }
+
+    /// 使用已获取的响应头创建网络媒体源
+    pub fn with_info(url: String, info: RemoteInfo) -> Self {
+        Self {
+            url,
+            reader: None,
+            position: 0,
+            content_length: info.content_length,
+            accept_ranges: info.accept_ranges,
+        }
     }
 
     /// 初始化读取器
-    fn ensure_reader(&mut self) -> std::result::Result<(), Box<dyn std::error::Error>> {
+    fn ensure_reader(&mut self) -> std::result::Result<(), PlayerError> {
         if self.reader.is_none() {
-            let response = ureq::get(&self.url).call()?;
+            // 已到达文件末尾，无需再请求
+            if self.content_length.is_some_and(|len| self.position >= len) {
+                self.reader = Some(Box::new(std::io::empty()));
+                return Ok(());
+            }
+
+            let response = if self.position > 0 {
+                let response = ureq::get(&self.url)
+                    .set("Range", &format!("bytes={}-", self.position))
+                    .call()?;
+                if response.status() != 206 {
+                    return Err(PlayerError::new(
+                        ErrorCode::HttpError,
+                        "Server ignored the Range request",
+                    ));
+                }
+                response
+            } else {
+                ureq::get(&self.url).call()?
+            };
             self.reader = Some(Box::new(response.into_reader()));
         }
         Ok(())
@@ -25,23 +101,25 @@ impl NetworkMediaSource {
 impl MediaSource for NetworkMediaSource {
     /// 是否可随机访问
     fn is_seekable(&self) -> bool {
-        false
+        self.accept_ranges && self.content_length.is_some()
     }
 
     /// 文件大小
     fn byte_len(&self) -> Option<u64> {
-        None
+        self.content_length
     }
 }
 
 impl std::io::Read for NetworkMediaSource {
     /// 读取
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.ensure_reader()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?;
+        // 保留原始错误，解码线程据此得到对应的错误码
+        self.ensure_reader().map_err(std::io::Error::other)?;
 
         if let Some(ref mut reader) = self.reader {
-            reader.read(buf)
+            let read = reader.read(buf)?;
+            self.position += read as u64;
+            Ok(read)
         } else {
             Ok(0)
         }
@@ -49,12 +127,40 @@ impl std::io::Read for NetworkMediaSource {
 }
 
 impl std::io::Seek for NetworkMediaSource {
-    fn seek(&mut self, _pos: std::io::SeekFrom) -> std::io::Result<u64> {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Unsupported,
-            "Network streams do not support seeking",
-        ))
-    }
-}
+    /// 定位，通过 Range 请求重新打开网络流
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            std::io::SeekFrom::Start(offset) => Some(offset),
+            std::io::SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            std::io::SeekFrom::End(offset) => self
+                .content_length
+                .and_then(|len| len.checked_add_signed(offset)),
+        };
+
+        let target = match target {
+            Some(target) => target,
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "Invalid seek position",
+                ))
+            }
+        };
 
+        if target == self.position {
+            return Ok(target);
+        }
+
+        if !self.is_seekable() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "Server does not support Range requests",
+            ));
+        }
 
+        // 丢弃旧的读取器，下一次读取时从新位置重新请求
+        self.reader = None;
+        self.position = target;
+        Ok(target)
+    }
+}
@@ -0,0 +1,216 @@
+/// 播放队列
+#[derive(Debug, Clone, Default)]
+pub struct PlayQueue {
+    /// 队列中的URL
+    entries: Vec<String>,
+    /// 当前播放项索引
+    current: Option<usize>,
+}
+
+impl PlayQueue {
+    /// 创建播放队列
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 队列长度
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 当前播放项索引
+    pub fn current_index(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// 获取指定位置的URL
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// 添加到队尾
+    pub fn enqueue(&mut self, url: String) -> usize {
+        self.entries.push(url);
+        self.entries.len() - 1
+    }
+
+    /// 移除指定位置的项
+    pub fn remove(&mut self, index: usize) -> Option<String> {
+        if index >= self.entries.len() {
+            return None;
+        }
+
+        let removed = self.entries.remove(index);
+
+        // 保持当前项索引指向同一首，移除当前项时回退一位，使下一首仍是原来的下一首
+        self.current = match self.current {
+            Some(current) if index < current => Some(current - 1),
+            Some(current) if index == current => current.checked_sub(1),
+            other => other,
+        };
+
+        Some(removed)
+    }
+
+    /// 移动项到新位置
+    pub fn move_entry(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.entries.len() || to >= self.entries.len() {
+            return false;
+        }
+
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+
+        self.current = self.current.map(|current| {
+            if current == from {
+                to
+            } else if from < current && current <= to {
+                current - 1
+            } else if to <= current && current < from {
+                current + 1
+            } else {
+                current
+            }
+        });
+
+        true
+    }
+
+    /// 清空队列
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.current = None;
+    }
+
+    /// 恢复当前项索引，切换的轨道无法播放时调用
+    pub fn restore_current(&mut self, current: Option<usize>) {
+        self.current = current.filter(|current| *current < self.entries.len());
+    }
+
+    /// 跳转到指定位置
+    pub fn jump_to(&mut self, index: usize) -> Option<String> {
+        let url = self.entries.get(index)?.clone();
+        self.current = Some(index);
+        Some(url)
+    }
+
+    /// 查看下一首，不移动当前项
+    pub fn peek_next(&self) -> Option<&str> {
+        let index = self.current.map_or(0, |current| current + 1);
+        self.get(index)
+    }
+
+    /// 下一首
+    pub fn next_entry(&mut self) -> Option<String> {
+        let index = self.current.map_or(0, |current| current + 1);
+        self.jump_to(index)
+    }
+
+    /// 上一首
+    pub fn previous_entry(&mut self) -> Option<String> {
+        let index = self.current?.checked_sub(1)?;
+        self.jump_to(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_queue(urls: &[&str], current: usize) -> PlayQueue {
+        let mut queue = PlayQueue::new();
+        for url in urls {
+            queue.enqueue(url.to_string());
+        }
+        queue.jump_to(current);
+        queue
+    }
+
+    fn current(queue: &PlayQueue) -> Option<&str> {
+        queue.current_index().and_then(|index| queue.get(index))
+    }
+
+    #[test]
+    fn remove_before_current_keeps_current_entry() {
+        let mut queue = new_queue(&["a", "b", "c", "d"], 2);
+        assert_eq!(queue.remove(0).as_deref(), Some("a"));
+        assert_eq!(current(&queue), Some("c"));
+        assert_eq!(queue.peek_next(), Some("d"));
+    }
+
+    #[test]
+    fn remove_after_current_keeps_current_entry() {
+        let mut queue = new_queue(&["a", "b", "c", "d"], 1);
+        queue.remove(2);
+        assert_eq!(current(&queue), Some("b"));
+        assert_eq!(queue.peek_next(), Some("d"));
+    }
+
+    #[test]
+    fn remove_current_keeps_next_entry() {
+        let mut queue = new_queue(&["a", "b", "c"], 1);
+        queue.remove(1);
+        assert_eq!(current(&queue), Some("a"));
+        assert_eq!(queue.next_entry().as_deref(), Some("c"));
+
+        let mut queue = new_queue(&["a", "b"], 0);
+        queue.remove(0);
+        assert_eq!(queue.current_index(), None);
+        assert_eq!(queue.next_entry().as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn remove_out_of_range() {
+        let mut queue = new_queue(&["a"], 0);
+        assert_eq!(queue.remove(1), None);
+        assert_eq!(queue.len(), 1);
+    }
+
+    #[test]
+    fn move_current_entry() {
+        let mut queue = new_queue(&["a", "b", "c", "d"], 1);
+        assert!(queue.move_entry(1, 3));
+        assert_eq!(current(&queue), Some("b"));
+        assert_eq!(queue.current_index(), Some(3));
+    }
+
+    #[test]
+    fn move_entry_across_current() {
+        let mut queue = new_queue(&["a", "b", "c", "d"], 2);
+        assert!(queue.move_entry(0, 3));
+        assert_eq!(current(&queue), Some("c"));
+        assert_eq!(queue.current_index(), Some(1));
+
+        let mut queue = new_queue(&["a", "b", "c", "d"], 1);
+        assert!(queue.move_entry(3, 0));
+        assert_eq!(current(&queue), Some("b"));
+        assert_eq!(queue.current_index(), Some(2));
+    }
+
+    #[test]
+    fn move_entry_elsewhere_keeps_current() {
+        let mut queue = new_queue(&["a", "b", "c", "d"], 0);
+        assert!(queue.move_entry(2, 3));
+        assert_eq!(queue.current_index(), Some(0));
+        assert_eq!(queue.peek_next(), Some("b"));
+        assert!(!queue.move_entry(0, 4));
+    }
+
+    #[test]
+    fn restore_current_after_failed_advance() {
+        let mut queue = new_queue(&["a", "b", "c"], 0);
+        let previous = queue.current_index();
+        assert_eq!(queue.next_entry().as_deref(), Some("b"));
+        queue.restore_current(previous);
+        assert_eq!(current(&queue), Some("a"));
+        assert_eq!(queue.peek_next(), Some("b"));
+
+        queue.restore_current(Some(5));
+        assert_eq!(queue.current_index(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use crate::audio::loudness::{LoudnessMeter, LoudnessStats};
+use crate::audio::replaygain::ReplayGainTags;
+use crate::error_codes::{ErrorCode, PlayerError};
+use crate::player::{LoopRegion, NetworkMediaSource, RemoteInfo};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    formats::{FormatOptions, FormatReader},
+    io::{MediaSource, MediaSourceStream},
+    meta::MetadataOptions,
+    probe::Hint,
+    errors::Error,
+    units::TimeBase,
+};
+
+/// 已探测并创建好解码器的轨道
+pub struct PreparedTrack {
+    /// 来源URL
+    pub url: String,
+    /// 格式读取器
+    pub reader: Box<dyn FormatReader>,
+    /// 解码器
+    pub decoder: Box<dyn Decoder>,
+    /// 轨道ID
+    pub track_id: u32,
+    /// 总时长(毫秒)
+    pub total_time_ms: Option<u64>,
+    /// 总帧数
+    pub n_frames: Option<u64>,
+    /// 时间基
+    pub time_base: Option<TimeBase>,
+    /// LOOPSTART/LOOPLENGTH 标签中的循环区间
+    pub loop_tags: Option<LoopRegion>,
+    /// ReplayGain/R128 标签中的回放增益
+    pub replay_gain: ReplayGainTags,
+    /// 来源是否支持定位
+    pub seekable: bool,
+}
+
+impl PreparedTrack {
+    /// 打开网络轨道并完成格式探测
+    pub fn open(url: &str) -> std::result::Result<Self, PlayerError> {
+        Self::open_source(url, NetworkMediaSource::new(url.to_string())?)
+    }
+
+    /// 使用已获取的响应头打开网络轨道，不再重复请求响应头
+    pub fn open_with_info(url: &str, info: RemoteInfo) -> std::result::Result<Self, PlayerError> {
+        Self::open_source(url, NetworkMediaSource::with_info(url.to_string(), info))
+    }
+
+    fn open_source(url: &str, source: NetworkMediaSource) -> std::result::Result<Self, PlayerError> {
+        let mut hint = Hint::new();
+        if url.ends_with(".mp3") {
+            hint.with_extension("mp3");
+        } else if url.ends_with(".flac") {
+            hint.with_extension("flac");
+        }
+
+        let seekable = source.is_seekable();
+        let mss = MediaSourceStream::new(Box::new(source), Default::default());
+
+        // 启用无缝播放，裁剪 LAME/Xing 头中记录的编码延迟和填充
+        let format_opts = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
+
+        let mut probed = symphonia::default::get_probe()
+            .format(&hint, mss, &format_opts, &MetadataOptions::default())?;
+
+        let mut reader = probed.format;
+
+        // 循环标签可能在容器前的 ID3 中，也可能在容器自身的元数据中
+        let loop_tags = probed
+            .metadata
+            .get()
+            .and_then(|metadata| metadata.current().and_then(|rev| LoopRegion::from_tags(rev.tags())))
+            .or_else(|| {
+                reader
+                    .metadata()
+                    .current()
+                    .and_then(|rev| LoopRegion::from_tags(rev.tags()))
+            });
+
+        // 回放增益标签同样可能在 ID3 或容器元数据中
+        let replay_gain = probed
+            .metadata
+            .get()
+            .and_then(|metadata| metadata.current().and_then(|rev| ReplayGainTags::from_tags(rev.tags())))
+            .or_else(|| {
+                reader
+                    .metadata()
+                    .current()
+                    .and_then(|rev| ReplayGainTags::from_tags(rev.tags()))
+            })
+            .unwrap_or_default();
+
+        let track = reader
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| {
+                PlayerError::new(ErrorCode::MediaFormatUnsupported, "No supported audio track")
+            })?;
+
+        let track_id = track.id;
+        let params = track.codec_params.clone();
+
+        // 获取总时长，优先使用时间基换算，没有时间基时使用采样率
+        let total_time_ms = match (params.n_frames, params.time_base, params.sample_rate) {
+            (Some(frames), Some(time_base), _) => {
+                let time = time_base.calc_time(frames);
+                Some(time.seconds * 1000 + (time.frac * 1000.0) as u64)
+            }
+            (Some(frames), None, Some(sample_rate)) => Some(frames * 1000 / sample_rate as u64),
+            _ => None,
+        };
+
+        let decoder = symphonia::default::get_codecs().make(&params, &DecoderOptions::default())?;
+
+        Ok(Self {
+            url: url.to_string(),
+            reader,
+            decoder,
+            track_id,
+            total_time_ms,
+            n_frames: params.n_frames,
+            time_base: params.time_base,
+            loop_tags,
+            replay_gain,
+            seekable,
+        })
+    }
+
+    /// 解码整条轨道，测量积分响度、响度范围和真峰值
+    pub fn analyze_loudness(&mut self) -> std::result::Result<LoudnessStats, PlayerError> {
+        let mut meter: Option<LoudnessMeter> = None;
+        let mut samples: Option<SampleBuffer<f32>> = None;
+
+        loop {
+            let packet = match self.reader.next_packet() {
+                Ok(packet) => packet,
+                Err(Error::IoError(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(Error::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let spec = *decoded.spec();
+            let meter = meter.get_or_insert_with(|| LoudnessMeter::new(spec.rate, spec.channels.count(), true));
+            let buf = match samples.as_mut() {
+                Some(buf) if buf.capacity() >= decoded.capacity() * spec.channels.count() => buf,
+                _ => samples.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            buf.copy_interleaved_ref(decoded);
+            meter.process(buf.samples());
+        }
+
+        Ok(meter.map(|meter| meter.stats()).unwrap_or_default())
+    }
+}